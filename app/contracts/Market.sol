@@ -11,15 +11,24 @@ contract Market is ReentrancyGuard {
     Counters.Counter private _itemIds;
     bytes32 private _thumbnailProgramId;
     bytes32 private _envelopeProgramId;
+    bytes32 private _deliveryProgramId;
     address private _alignedManagerContract;
 
+    // Envelope journal: imageHash (32) | blobCommitment (32) | publicKey (33) | buyer (20) | nonce (12)
+    //     | namespace (29) | shareVersion (1) | signer (20)
+    // Delivery journal: imageHash (32) | dataRoot (32) | publicKey (33) | buyer (20) | nonce (12)
+    //     | blobCommitment (32) | namespace (29) | shareVersion (1) | signer (20)
+    uint256 private constant JOURNAL_BUYER_OFFSET = 97;
+
     constructor(
         bytes32 thumbnailProgramId,
         bytes32 envelopeProgramId,
+        bytes32 deliveryProgramId,
         address alignedManagerContract
     ) {
         _thumbnailProgramId = thumbnailProgramId;
         _envelopeProgramId = envelopeProgramId;
+        _deliveryProgramId = deliveryProgramId;
         _alignedManagerContract = alignedManagerContract;
     }
 
@@ -166,9 +175,37 @@ contract Market is ReentrancyGuard {
         idToMarketItem[itemId].status = ItemStatus.InEscrow;
     }
 
+    function journalImageHash(bytes memory journal) internal pure returns (bytes32 imageHash) {
+        require(journal.length >= 32, "Invalid journal length");
+        assembly {
+            imageHash := mload(add(journal, 32))
+        }
+    }
+
+    // Listed image hash is either the raw SHA-256 digest or a multihash ending with it
+    function matchesImageHash(bytes memory listed, bytes32 imageHash) internal pure returns (bool) {
+        if (listed.length < 32) {
+            return false;
+        }
+        bytes32 digest;
+        uint256 offset = listed.length;
+        assembly {
+            digest := mload(add(listed, offset))
+        }
+        return digest == imageHash;
+    }
+
+    function journalBuyer(bytes memory journal) internal pure returns (address buyer) {
+        require(journal.length >= JOURNAL_BUYER_OFFSET + 20, "Invalid journal length");
+        uint256 offset = 32 + JOURNAL_BUYER_OFFSET;
+        assembly {
+            buyer := shr(96, mload(add(journal, offset)))
+        }
+    }
+
     function deliverMarketItem(
         uint256 itemId,
-        bytes memory publicKey,
+        bytes memory journal,
         // verification data,
         bytes32 proofCommitment,
         bytes32 pubInputCommitment,
@@ -180,11 +217,15 @@ contract Market is ReentrancyGuard {
     ) public nonReentrant {
         require(idToMarketItem[itemId].status == ItemStatus.InEscrow);
 
-        // bytes32 publicKeyHash = keccak256(publicKey);
-        // address addr = address(uint160(uint256(publicKeyHash)));
-        // require(idToMarketItem[itemId].buyer == addr);
+        // Aligned commits to keccak256 of the public inputs, i.e. the journal
+        require(keccak256(journal) == pubInputCommitment, "Journal does not match the proof");
+        require(idToMarketItem[itemId].buyer == journalBuyer(journal), "Delivered to a wrong buyer");
+        require(
+            matchesImageHash(idToMarketItem[itemId].imageHash, journalImageHash(journal)),
+            "Delivered a wrong image"
+        );
 
-        // require(_envelopeProgramId == provingSystemAuxDataCommitment, "Image ID does not match");
+        require(_deliveryProgramId == provingSystemAuxDataCommitment, "Image ID does not match");
 
         require(
             checkProofVerification(
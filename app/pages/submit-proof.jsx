@@ -14,7 +14,7 @@ export default function SubmitProof() {
 	}, []);
 
 	const [formInput, setFormInput] = useState({
-		publicKey: '',
+		journal: '',
 		verificationData: '',
 		publicationData: '',
 	});
@@ -53,9 +53,9 @@ export default function SubmitProof() {
 	}
 
 	const finalizeItem = async () => {
-		const { publicKey, verificationData, publicationData } = formInput;
-		console.log("New item: ", publicKey, verificationData, publicationData);
-		if (!publicKey || !verificationData || !publicationData) return;
+		const { journal, verificationData, publicationData } = formInput;
+		console.log("New item: ", journal, verificationData, publicationData);
+		if (!journal || !verificationData || !publicationData) return;
 
 		const modal = new Web3Modal();
 		const connection = await modal.connect();
@@ -76,7 +76,7 @@ export default function SubmitProof() {
 		
 		const marketTransaction = await marketContract.deliverMarketItem(
 			item.itemId,
-			publicKey,
+			journal,
 			...decodeVerificationData(verificationData),
 			{ maxFeePerGas: 970000000 }
 		);
@@ -96,9 +96,9 @@ export default function SubmitProof() {
 				<label className='mt-4' style={{color: '#444'}}>Use public key of the buyer <code>{item.buyer}</code> for encryption.</label>
 				<input
 					className='mt-8 border rounded p-4'
-					placeholder='Public key of the buyer'
+					placeholder='Journal of the delivery proof in hex [provided by stock0 verify]'
 					onChange={(e) =>
-						setFormInput((prev) => ({ ...prev, publicKey: e.target.value }))
+						setFormInput((prev) => ({ ...prev, journal: e.target.value }))
 					}
 				/>
 				<textarea
@@ -128,7 +128,7 @@ export default function SubmitProof() {
 					disabled={
 						!formInput.verificationData ||
 						!formInput.publicationData ||
-						!formInput.publicKey
+						!formInput.journal
 					}
 				>
 					Deliver image
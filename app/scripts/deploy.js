@@ -23,10 +23,11 @@ async function main() {
 	const Market = await hre.ethers.getContractFactory('Market');
 	console.log("Market factory ", Market);
 
-	// TODO: thumbnail and envelope program (image) IDs
+	// TODO: thumbnail, envelope and delivery program (image) IDs
 	const market = await Market.deploy(
 		'0x2d66053b72f48e96685b2d3808fe091b1a994b7543a980d60affea2b5731e5af',
 		'0x0000000000000000000000000000000000000000000000000000000000000000',
+		'0x0000000000000000000000000000000000000000000000000000000000000000',
 		aligned.address
 	);
 	await market.deployed();
@@ -17,8 +17,9 @@ async function main() {
 	const Market = await hre.ethers.getContractFactory('Market');
 	console.log("Market factory ", Market);
 
-	// TODO: thumbnail and envelope program (image) IDs
+	// TODO: thumbnail, envelope and delivery program (image) IDs
 	const market = await Market.deploy(
+		'0x0000000000000000000000000000000000000000000000000000000000000000',
 		'0x0000000000000000000000000000000000000000000000000000000000000000',
 		'0x0000000000000000000000000000000000000000000000000000000000000000',
 		'0x58F280BeBE9B34c9939C3C39e0890C81f163B623'
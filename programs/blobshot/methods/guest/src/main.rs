@@ -1,6 +1,8 @@
-use celestia_types::{nmt::{MerkleHash, Namespace, NamespaceProof, NamespacedHashExt}, Commitment, Share};
+use celestia_types::{nmt::{MerkleHash, NamespaceProof, NamespacedHashExt}, Commitment, Share};
 use nmt_rs::{simple_merkle::proof::Proof, NamespacedHash, TmSha2Hasher};
 use risc0_zkvm::guest::env;
+use stock0_celestia::NAMESPACE_SIZE;
+use stock0_journal::{BlobshotJournal, Journal};
 
 fn main() {
     // read the data root
@@ -10,8 +12,10 @@ fn main() {
     let num_rows: u32 = env::read();
     // read blob size
     let blob_size: u32 = env::read();
-    // read namespace ID
-    let namespace: Namespace = env::read();
+    // read namespace
+    let mut namespace = [0u8; NAMESPACE_SIZE];
+    env::read_slice(&mut namespace);
+    let namespace = stock0_celestia::parse_namespace(&namespace).expect("Invalid namespace");
     // read the row-inclusion range proof
     let range_proof: Proof<TmSha2Hasher> = env::read();
     // read the row roots
@@ -65,5 +69,10 @@ fn main() {
     let blob_commitment = Commitment::from_shares(namespace, &blob_shares)
         .expect("Failed to create commitment");
 
-    env::commit_slice(&[data_root, blob_commitment.0].concat());
+    let journal = BlobshotJournal {
+        data_root,
+        blob_commitment: blob_commitment.0,
+        namespace: stock0_celestia::namespace_bytes(&namespace),
+    };
+    env::commit_slice(&journal.to_bytes());
 }
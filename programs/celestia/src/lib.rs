@@ -0,0 +1,556 @@
+//! Celestia helpers shared by the host and the guest programs.
+//!
+//! The namespace is an input of every program that produces or proves a blob,
+//! so that a marketplace deployment can pick its own. Both sides validate it
+//! with the same rules: version 0 and not one of the reserved namespaces.
+//!
+//! Blobs are split into shares of version 0, or version 1 where the first share
+//! also carries the address of the account that paid for the blob (signer).
+//! The commitment is computed over the resulting shares, so it matches the one
+//! returned by the node for either version. [`ShareSplitter`] and [`BlobCommitter`]
+//! do the same incrementally, so that a blob never has to be held in memory at once.
+
+use std::fmt;
+
+use celestia_types::nmt::{MerkleHash, Namespace, NamespacedHashExt, Nmt, NAMESPACED_HASH_SIZE};
+use celestia_types::{Commitment, InfoByte, Share};
+use nmt_rs::TmSha2Hasher;
+
+/// Size of a namespace (version + ID)
+pub const NAMESPACE_SIZE: usize = 29;
+
+/// Max size of a version 0 namespace ID
+pub const NAMESPACE_V0_ID_SIZE: usize = 10;
+
+/// Size of a share
+pub const SHARE_SIZE: usize = 512;
+
+/// Size of the signer address in version 1 shares
+pub const SIGNER_SIZE: usize = 20;
+
+/// Size of the sequence length prefix in the first share
+const SEQUENCE_LEN_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    InvalidNamespace,
+    UnsupportedNamespaceVersion(u8),
+    ReservedNamespace,
+    UnsupportedShareVersion(u8),
+    BlobTooLarge,
+    BlobLengthMismatch,
+    InvalidShares,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidNamespace => write!(
+                f,
+                "namespace must be {NAMESPACE_SIZE} bytes, with an ID of up to {NAMESPACE_V0_ID_SIZE} bytes"
+            ),
+            Error::UnsupportedNamespaceVersion(v) => {
+                write!(f, "unsupported namespace version {v}, only 0 is allowed")
+            }
+            Error::ReservedNamespace => write!(f, "namespace is reserved"),
+            Error::UnsupportedShareVersion(v) => {
+                write!(f, "unsupported share version {v}, only 0 and 1 are allowed")
+            }
+            Error::BlobTooLarge => write!(f, "blob must be less than 4 GiB"),
+            Error::BlobLengthMismatch => write!(f, "blob length does not match the declared one"),
+            Error::InvalidShares => write!(f, "failed to compute the share commitment"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Version 0 namespace from its (up to 10 bytes) ID
+pub fn namespace_v0(id: &[u8]) -> Result<Namespace, Error> {
+    if id.is_empty() || id.len() > NAMESPACE_V0_ID_SIZE {
+        return Err(Error::InvalidNamespace);
+    }
+    let namespace = Namespace::new_v0(id).map_err(|_| Error::InvalidNamespace)?;
+    validate_namespace(namespace)
+}
+
+/// Namespace from its raw bytes, as passed to the guests and committed in the journals
+pub fn parse_namespace(bytes: &[u8]) -> Result<Namespace, Error> {
+    if bytes.len() != NAMESPACE_SIZE {
+        return Err(Error::InvalidNamespace);
+    }
+    let namespace = Namespace::from_raw(bytes).map_err(|_| Error::InvalidNamespace)?;
+    validate_namespace(namespace)
+}
+
+/// Checks that blobs can be published under the namespace
+pub fn validate_namespace(namespace: Namespace) -> Result<Namespace, Error> {
+    let bytes = namespace.as_bytes();
+    if bytes[0] != 0 {
+        return Err(Error::UnsupportedNamespaceVersion(bytes[0]));
+    }
+    // Primary reserved namespaces are version 0 with all ID bytes but the last one zeroed
+    if bytes[1..NAMESPACE_SIZE - 1].iter().all(|b| *b == 0) {
+        return Err(Error::ReservedNamespace);
+    }
+    Ok(namespace)
+}
+
+/// Raw namespace bytes, the form committed in the journals
+pub fn namespace_bytes(namespace: &Namespace) -> [u8; NAMESPACE_SIZE] {
+    namespace
+        .as_bytes()
+        .try_into()
+        .expect("namespace is 29 bytes")
+}
+
+/// Share format of the blob
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareVersion {
+    V0,
+    /// The first share carries the signer address
+    V1 {
+        signer: [u8; SIGNER_SIZE],
+    },
+}
+
+impl ShareVersion {
+    /// Parses the version byte and the signer (required for version 1 only)
+    pub fn new(version: u8, signer: [u8; SIGNER_SIZE]) -> Result<Self, Error> {
+        match version {
+            0 => Ok(ShareVersion::V0),
+            1 => Ok(ShareVersion::V1 { signer }),
+            _ => Err(Error::UnsupportedShareVersion(version)),
+        }
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            ShareVersion::V0 => 0,
+            ShareVersion::V1 { .. } => 1,
+        }
+    }
+
+    /// Signer address, zeroed for version 0
+    pub fn signer(&self) -> [u8; SIGNER_SIZE] {
+        match self {
+            ShareVersion::V0 => [0u8; SIGNER_SIZE],
+            ShareVersion::V1 { signer } => *signer,
+        }
+    }
+}
+
+/// Splits the blob into sparse shares
+pub fn split_blob_to_shares(
+    namespace: Namespace,
+    version: ShareVersion,
+    data: &[u8],
+) -> Result<Vec<[u8; SHARE_SIZE]>, Error> {
+    let mut splitter = ShareSplitter::new(namespace, version, data.len())?;
+    let mut shares = Vec::with_capacity(splitter.num_shares());
+    splitter.push(data, &mut shares)?;
+    splitter.finish(&mut shares)?;
+    Ok(shares)
+}
+
+/// Number of shares of a blob of the given length
+pub fn blob_share_count(version: ShareVersion, data_len: usize) -> usize {
+    let first_share_capacity = SHARE_SIZE - first_share_prefix_len(version);
+    let share_capacity = SHARE_SIZE - NAMESPACE_SIZE - 1;
+    1 + data_len
+        .saturating_sub(first_share_capacity)
+        .div_ceil(share_capacity)
+}
+
+/// Namespace, info byte, sequence length and signer (version 1)
+fn first_share_prefix_len(version: ShareVersion) -> usize {
+    let signer_len = match version {
+        ShareVersion::V0 => 0,
+        ShareVersion::V1 { .. } => SIGNER_SIZE,
+    };
+    NAMESPACE_SIZE + 1 + SEQUENCE_LEN_SIZE + signer_len
+}
+
+/// Incremental [`split_blob_to_shares`] for a blob of known length fed in pieces,
+/// completed shares are appended to the output as soon as they are full
+pub struct ShareSplitter {
+    namespace: Namespace,
+    version: ShareVersion,
+    data_len: u32,
+    /// Bytes of the blob consumed so far
+    consumed: usize,
+    share: [u8; SHARE_SIZE],
+    /// Write position in the current share, `None` if the next share is not started yet
+    offset: Option<usize>,
+    emitted: usize,
+}
+
+impl ShareSplitter {
+    pub fn new(
+        namespace: Namespace,
+        version: ShareVersion,
+        data_len: usize,
+    ) -> Result<Self, Error> {
+        let data_len: u32 = data_len.try_into().map_err(|_| Error::BlobTooLarge)?;
+        InfoByte::new(version.as_u8(), true)
+            .map_err(|_| Error::UnsupportedShareVersion(version.as_u8()))?;
+        Ok(Self {
+            namespace,
+            version,
+            data_len,
+            consumed: 0,
+            share: [0u8; SHARE_SIZE],
+            offset: None,
+            emitted: 0,
+        })
+    }
+
+    /// Total number of shares of the blob
+    pub fn num_shares(&self) -> usize {
+        blob_share_count(self.version, self.data_len as usize)
+    }
+
+    /// Splits the next piece of the blob
+    pub fn push(
+        &mut self,
+        mut data: &[u8],
+        shares: &mut Vec<[u8; SHARE_SIZE]>,
+    ) -> Result<(), Error> {
+        if self.consumed + data.len() > self.data_len as usize {
+            return Err(Error::BlobLengthMismatch);
+        }
+        self.consumed += data.len();
+
+        while !data.is_empty() {
+            let offset = match self.offset {
+                Some(offset) => offset,
+                None => self.start_share(),
+            };
+            let read_amount = (SHARE_SIZE - offset).min(data.len());
+            self.share[offset..offset + read_amount].copy_from_slice(&data[..read_amount]);
+            data = &data[read_amount..];
+            self.offset = Some(offset + read_amount);
+
+            if offset + read_amount == SHARE_SIZE {
+                shares.push(self.share);
+                self.emitted += 1;
+                self.offset = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flushes the last share (padded with zeros), the whole blob must have been pushed
+    pub fn finish(mut self, shares: &mut Vec<[u8; SHARE_SIZE]>) -> Result<(), Error> {
+        if self.consumed != self.data_len as usize {
+            return Err(Error::BlobLengthMismatch);
+        }
+        // An empty blob still takes one share
+        if self.emitted == 0 && self.offset.is_none() {
+            self.start_share();
+        }
+        if self.offset.is_some() {
+            shares.push(self.share);
+        }
+        Ok(())
+    }
+
+    /// Writes the share prefix, returns the offset of the data
+    fn start_share(&mut self) -> usize {
+        let is_first_share = self.emitted == 0;
+        self.share = [0u8; SHARE_SIZE];
+
+        // Namespace and info byte
+        self.share[..NAMESPACE_SIZE].copy_from_slice(self.namespace.as_bytes());
+        let info_byte = InfoByte::new(self.version.as_u8(), is_first_share)
+            .expect("share version is checked in ShareSplitter::new");
+        self.share[NAMESPACE_SIZE] = info_byte.as_u8();
+        let mut offset = NAMESPACE_SIZE + 1;
+
+        // The first share of the sequence carries its length (and signer in version 1)
+        if is_first_share {
+            self.share[offset..offset + SEQUENCE_LEN_SIZE]
+                .copy_from_slice(&self.data_len.to_be_bytes());
+            offset += SEQUENCE_LEN_SIZE;
+            if let ShareVersion::V1 { signer } = self.version {
+                self.share[offset..offset + SIGNER_SIZE].copy_from_slice(&signer);
+                offset += SIGNER_SIZE;
+            }
+        }
+        self.offset = Some(offset);
+        offset
+    }
+}
+
+/// Share commitment of the blob, as returned by `blob.Get`
+pub fn blob_commitment(
+    namespace: Namespace,
+    shares: &[[u8; SHARE_SIZE]],
+) -> Result<[u8; 32], Error> {
+    let shares = shares
+        .iter()
+        .map(|share| Share::from_raw(share).map_err(|_| Error::InvalidShares))
+        .collect::<Result<Vec<_>, _>>()?;
+    let commitment =
+        Commitment::from_shares(namespace, &shares).map_err(|_| Error::InvalidShares)?;
+    Ok(commitment.0)
+}
+
+/// Incremental [`blob_commitment`], the shares are pushed in order as they are produced.
+///
+/// The commitment is the Merkle root (RFC 6962) over the roots of the NMT subtrees the blob
+/// is split into (merkle mountain range of subtrees of width determined by the share count),
+/// so only the subtree in progress is kept in memory.
+pub struct BlobCommitter {
+    namespace: Namespace,
+    /// Sizes of the subtrees left, in reverse order
+    subtree_sizes: Vec<usize>,
+    subtree: Nmt,
+    subtree_len: usize,
+    subtree_roots: Vec<[u8; NAMESPACED_HASH_SIZE]>,
+}
+
+impl BlobCommitter {
+    pub fn new(namespace: Namespace, num_shares: usize) -> Self {
+        let mut subtree_sizes = merkle_mountain_range_sizes(
+            num_shares,
+            subtree_width(num_shares, SUBTREE_ROOT_THRESHOLD),
+        );
+        subtree_sizes.reverse();
+        Self {
+            namespace,
+            subtree_sizes,
+            subtree: Nmt::new(),
+            subtree_len: 0,
+            subtree_roots: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, share: &[u8; SHARE_SIZE]) -> Result<(), Error> {
+        let subtree_size = *self.subtree_sizes.last().ok_or(Error::InvalidShares)?;
+        self.subtree
+            .push_leaf(share, self.namespace.into())
+            .map_err(|_| Error::InvalidShares)?;
+        self.subtree_len += 1;
+
+        if self.subtree_len == subtree_size {
+            let mut subtree = std::mem::replace(&mut self.subtree, Nmt::new());
+            self.subtree_roots.push(subtree.root().to_array());
+            self.subtree_sizes.pop();
+            self.subtree_len = 0;
+        }
+        Ok(())
+    }
+
+    /// All of the declared shares must have been pushed
+    pub fn finalize(self) -> Result<[u8; 32], Error> {
+        if !self.subtree_sizes.is_empty() {
+            return Err(Error::InvalidShares);
+        }
+        Ok(merkle_root(&TmSha2Hasher {}, &self.subtree_roots))
+    }
+}
+
+/// Subtree root threshold of the share commitment (`appconsts.SubtreeRootThreshold`)
+pub const SUBTREE_ROOT_THRESHOLD: usize = 64;
+
+/// Width of the commitment subtrees: enough to keep the number of subtree roots
+/// under the threshold, but not wider than the smallest square fitting the blob.
+/// Blobs are aligned to it in the data square (non-interactive default rules).
+pub fn subtree_width(num_shares: usize, threshold: usize) -> usize {
+    let width = num_shares.div_ceil(threshold).next_power_of_two();
+    width.min(min_square_size(num_shares))
+}
+
+/// Smallest power of two square width that fits the shares
+pub fn min_square_size(num_shares: usize) -> usize {
+    let mut width = 1;
+    while width * width < num_shares {
+        width *= 2;
+    }
+    width
+}
+
+/// Splits the shares into subtrees of `max_tree_size`, the rest into decreasing powers of two
+fn merkle_mountain_range_sizes(mut num_shares: usize, max_tree_size: usize) -> Vec<usize> {
+    let mut sizes = Vec::new();
+    while num_shares != 0 {
+        let size = if num_shares >= max_tree_size {
+            max_tree_size
+        } else {
+            1 << num_shares.ilog2()
+        };
+        sizes.push(size);
+        num_shares -= size;
+    }
+    sizes
+}
+
+/// RFC 6962 Merkle root (same as the Tendermint simple Merkle tree)
+fn merkle_root(hasher: &TmSha2Hasher, leaves: &[[u8; NAMESPACED_HASH_SIZE]]) -> [u8; 32] {
+    match leaves.len() {
+        0 => TmSha2Hasher::EMPTY_ROOT,
+        1 => hasher.hash_leaf(&leaves[0]),
+        len => {
+            // Largest power of two smaller than the number of leaves
+            let split = 1 << (len - 1).ilog2();
+            let left = merkle_root(hasher, &leaves[..split]);
+            let right = merkle_root(hasher, &leaves[split..]);
+            hasher.hash_nodes(&left, &right)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use celestia_types::{Blob, Commitment};
+
+    use super::*;
+
+    /// Data bytes of a continuation share
+    const SHARE_CAPACITY: usize = SHARE_SIZE - NAMESPACE_SIZE - 1;
+
+    const SIGNER: ShareVersion = ShareVersion::V1 {
+        signer: [9; SIGNER_SIZE],
+    };
+
+    fn namespace() -> Namespace {
+        namespace_v0(&[1, 2, 3, 4, 5]).unwrap()
+    }
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 % 253) as u8).collect()
+    }
+
+    /// Lengths around the share boundaries and the changes of the subtree width
+    fn lengths(version: ShareVersion) -> Vec<usize> {
+        let first_share_capacity = SHARE_SIZE - first_share_prefix_len(version);
+        let mut lengths = vec![1, 2];
+        for num_shares in [1, 2, 3, 63, 64, 65, 127, 128, 129, 1024, 4097] {
+            let len = first_share_capacity + (num_shares - 1) * SHARE_CAPACITY;
+            lengths.extend([len - 1, len, len + 1]);
+        }
+        lengths
+    }
+
+    fn commit_incrementally(namespace: Namespace, shares: &[[u8; SHARE_SIZE]]) -> [u8; 32] {
+        let mut committer = BlobCommitter::new(namespace, shares.len());
+        for share in shares {
+            committer.push(share).unwrap();
+        }
+        committer.finalize().unwrap()
+    }
+
+    #[test]
+    fn v0_matches_celestia_types() {
+        for len in lengths(ShareVersion::V0) {
+            let data = data(len);
+            let shares = split_blob_to_shares(namespace(), ShareVersion::V0, &data).unwrap();
+            assert_eq!(shares.len(), blob_share_count(ShareVersion::V0, len));
+
+            let blob = Blob::new(namespace(), data.clone()).unwrap();
+            let expected: Vec<[u8; SHARE_SIZE]> = blob
+                .to_shares()
+                .unwrap()
+                .iter()
+                .map(|share| share.data)
+                .collect();
+            assert_eq!(shares, expected, "{len} bytes");
+
+            let commitment = Commitment::from_blob(namespace(), 0, &data).unwrap().0;
+            assert_eq!(blob_commitment(namespace(), &shares).unwrap(), commitment);
+            assert_eq!(commit_incrementally(namespace(), &shares), commitment);
+        }
+    }
+
+    /// `Blob` of celestia-types only produces version 0 shares, so the version 1 layout is
+    /// checked field by field and the commitment against `Commitment::from_shares`
+    #[test]
+    fn v1_layout() {
+        let signer = SIGNER.signer();
+        for len in lengths(SIGNER) {
+            let data = data(len);
+            let shares = split_blob_to_shares(namespace(), SIGNER, &data).unwrap();
+            assert_eq!(shares.len(), blob_share_count(SIGNER, len));
+
+            // Namespace | info byte | sequence length | signer | data
+            let first = &shares[0];
+            assert_eq!(&first[..NAMESPACE_SIZE], namespace().as_bytes());
+            assert_eq!(
+                first[NAMESPACE_SIZE],
+                InfoByte::new(1, true).unwrap().as_u8()
+            );
+            assert_eq!(first[NAMESPACE_SIZE + 1..][..4], (len as u32).to_be_bytes());
+            assert_eq!(first[NAMESPACE_SIZE + 5..][..SIGNER_SIZE], signer);
+
+            // Namespace | info byte | data, the last share is zero padded
+            let mut content = first[first_share_prefix_len(SIGNER)..].to_vec();
+            for share in &shares[1..] {
+                assert_eq!(&share[..NAMESPACE_SIZE], namespace().as_bytes());
+                assert_eq!(
+                    share[NAMESPACE_SIZE],
+                    InfoByte::new(1, false).unwrap().as_u8()
+                );
+                content.extend_from_slice(&share[NAMESPACE_SIZE + 1..]);
+            }
+            assert_eq!(content[..len], data);
+            assert!(content[len..].iter().all(|b| *b == 0));
+
+            // Commitment is computed over the shares the same way for both versions
+            let commitment = blob_commitment(namespace(), &shares).unwrap();
+            assert_eq!(commit_incrementally(namespace(), &shares), commitment);
+            let v0 = split_blob_to_shares(namespace(), ShareVersion::V0, &data).unwrap();
+            assert_ne!(blob_commitment(namespace(), &v0).unwrap(), commitment);
+        }
+    }
+
+    #[test]
+    fn splitter_matches_one_shot() {
+        for version in [ShareVersion::V0, SIGNER] {
+            let data = data(100_000);
+            let expected = split_blob_to_shares(namespace(), version, &data).unwrap();
+
+            for chunk_size in [1, 457, 458, 477, 478, 482, 1000, 16384] {
+                let mut splitter = ShareSplitter::new(namespace(), version, data.len()).unwrap();
+                let mut shares = Vec::new();
+                for chunk in data.chunks(chunk_size) {
+                    splitter.push(chunk, &mut shares).unwrap();
+                    // Only full shares are emitted before the end
+                    assert!(shares.len() < expected.len());
+                }
+                splitter.finish(&mut shares).unwrap();
+                assert_eq!(shares, expected, "{version:?} in chunks of {chunk_size}");
+            }
+        }
+    }
+
+    #[test]
+    fn splitter_checks_length() {
+        let mut shares = Vec::new();
+        let mut splitter = ShareSplitter::new(namespace(), ShareVersion::V0, 10).unwrap();
+        splitter.push(&[0; 5], &mut shares).unwrap();
+        assert_eq!(
+            splitter.push(&[0; 6], &mut shares),
+            Err(Error::BlobLengthMismatch)
+        );
+        assert_eq!(splitter.finish(&mut shares), Err(Error::BlobLengthMismatch));
+
+        // Empty blob still takes a share
+        let shares = split_blob_to_shares(namespace(), ShareVersion::V0, &[]).unwrap();
+        assert_eq!(shares.len(), 1);
+        assert_eq!(blob_share_count(ShareVersion::V0, 0), 1);
+    }
+
+    #[test]
+    fn committer_checks_share_count() {
+        let shares = split_blob_to_shares(namespace(), ShareVersion::V0, &data(2000)).unwrap();
+
+        let mut committer = BlobCommitter::new(namespace(), shares.len());
+        committer.push(&shares[0]).unwrap();
+        assert_eq!(committer.finalize(), Err(Error::InvalidShares));
+
+        let mut committer = BlobCommitter::new(namespace(), 1);
+        committer.push(&shares[0]).unwrap();
+        assert_eq!(committer.push(&shares[1]), Err(Error::InvalidShares));
+    }
+}
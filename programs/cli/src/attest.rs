@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use stock0_core::blobstream::{parse_hash, ATTESTATION_PROOF_FILE};
+use stock0_core::rpc::DEFAULT_RPC_URL;
+use stock0_core::{read_receipt, AttestationProof, CelestiaClient, DataCommitment, Mode};
+use stock0_journal::{DeliveryJournal, Journal};
+
+use crate::OutputArgs;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Delivery receipt, the data root is taken from its journal
+    #[arg(long)]
+    receipt: PathBuf,
+
+    /// Height of the block the blob was included in
+    #[arg(long)]
+    height: u64,
+
+    /// Nonce of the Blobstream data commitment that covers the block
+    #[arg(long)]
+    nonce: u64,
+
+    /// First block of the data commitment range
+    #[arg(long)]
+    start_block: u64,
+
+    /// End of the data commitment range (exclusive)
+    #[arg(long)]
+    end_block: u64,
+
+    /// Data commitment stored by Blobstream under the nonce, hex
+    #[arg(long)]
+    data_commitment: String,
+
+    /// celestia-node JSON-RPC endpoint the data roots of the range are fetched from
+    #[arg(long, default_value = DEFAULT_RPC_URL)]
+    rpc_url: String,
+
+    /// Node auth token (`celestia light auth read`)
+    #[arg(long, env = "CELESTIA_NODE_AUTH_TOKEN", hide_env_values = true)]
+    auth_token: Option<String>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+pub fn run(args: Args, mode: Mode) -> Result<()> {
+    let receipt = read_receipt(&args.receipt)?;
+    let journal = DeliveryJournal::from_bytes(&receipt.journal.bytes)
+        .context("Receipt is not a delivery receipt")?;
+
+    let commitment = DataCommitment {
+        proof_nonce: args.nonce,
+        start_block: args.start_block,
+        end_block: args.end_block,
+        data_commitment: parse_hash(&args.data_commitment)?,
+    };
+
+    let client = CelestiaClient::new(&args.rpc_url, args.auth_token);
+    let data_roots = client.get_data_roots(commitment.start_block..commitment.end_block)?;
+    let proof = AttestationProof::build(&commitment, &data_roots, args.height, journal.data_root)?;
+
+    println!("HEIGHT: {}", args.height);
+    println!("DATA ROOT: {}", hex::encode(journal.data_root));
+    println!("TUPLE ROOT NONCE: {}", proof.tuple_root_nonce);
+
+    let artifacts = args.output.writer("blobstream", mode)?;
+    let path = artifacts.write(
+        ATTESTATION_PROOF_FILE,
+        serde_json::to_string_pretty(&proof.to_json())?.as_bytes(),
+    )?;
+    println!("Attestation proof: {}", path.display());
+    Ok(())
+}
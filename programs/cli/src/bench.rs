@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use risc0_zkvm::{default_executor, ExecutorEnv};
+use stock0_format::ImageFormat;
+use thumbnail_methods::THUMBNAIL_GEN_ELF;
+
+use crate::thumbnail::SpecArgs;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Images to run the thumbnail program on (TIFF, PNG or JPEG)
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    #[command(flatten)]
+    spec: SpecArgs,
+}
+
+/// Executes the thumbnail program (no proof) on every image and prints its cycle counts,
+/// proving time and memory grow with the total cycles
+pub fn run(args: Args) -> Result<()> {
+    let spec = args.spec.spec()?;
+    println!("THUMBNAIL SPEC: {spec}");
+    println!(
+        "{:<40} {:>10} {:>10} {:>12} {:>12} {:>8}",
+        "IMAGE", "FORMAT", "BYTES", "USER CYCLES", "TOTAL CYCLES", "SEGMENTS"
+    );
+    for input in &args.inputs {
+        let image_bytes =
+            std::fs::read(input).with_context(|| format!("Failed to read {}", input.display()))?;
+        let image_format = ImageFormat::sniff(&image_bytes)
+            .with_context(|| format!("Unsupported image format of {}", input.display()))?;
+
+        let env = ExecutorEnv::builder()
+            .write_slice(&spec.to_bytes())
+            .write_slice(&image_bytes)
+            .stdout(std::io::sink())
+            .build()?;
+        let session = default_executor()
+            .execute(env, THUMBNAIL_GEN_ELF)
+            .with_context(|| format!("Thumbnail program failed on {}", input.display()))?;
+
+        // Segments are padded to a power of two cycles
+        let user_cycles: u64 = session.segments.iter().map(|s| s.cycles as u64).sum();
+        let total_cycles: u64 = session.segments.iter().map(|s| 1u64 << s.po2).sum();
+
+        println!(
+            "{:<40} {:>10} {:>10} {:>12} {:>12} {:>8}",
+            input.display(),
+            image_format.mime_type(),
+            image_bytes.len(),
+            user_cycles,
+            total_cycles,
+            session.segments.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context, Result};
+use base64::Engine;
+use blobshot_methods::{BLOB_ELF, BLOB_ID};
+use celestia_types::nmt::Namespace;
+use risc0_zkvm::{default_prover, ExecutorEnv, Receipt};
+use stock0_celestia::ShareVersion;
+use stock0_core::{DataRootTree, InclusionInputs, Mode};
+use stock0_journal::{BlobshotJournal, Journal};
+
+use crate::{NamespaceArgs, OutputArgs, ShareArgs};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Blob data
+    #[arg(long)]
+    blob: PathBuf,
+
+    /// Extended header of the block containing the blob (protobuf)
+    #[arg(long)]
+    header: PathBuf,
+
+    /// Share proofs as returned by `blob.GetProof` (JSON)
+    #[arg(long)]
+    proofs: PathBuf,
+
+    /// Index of the first share of the blob in the original data square (row-major),
+    /// as returned by `blob.Get`
+    #[arg(long)]
+    blob_index: usize,
+
+    #[command(flatten)]
+    namespace: NamespaceArgs,
+
+    #[command(flatten)]
+    share: ShareArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+pub fn run(args: Args, mode: Mode) -> Result<()> {
+    let my_namespace = args.namespace.namespace()?;
+    let share_version = args.share.share_version()?;
+
+    // Load header and share proofs ---------
+
+    let inputs = InclusionInputs::load(&args.header, &args.proofs, args.blob_index)?;
+
+    // Load blob ----------------------------
+
+    let blob_bytes = std::fs::read(&args.blob)
+        .with_context(|| format!("Failed to read {}", args.blob.display()))?;
+
+    let receipt = prove(&blob_bytes, my_namespace, share_version, &inputs)?;
+
+    let artifacts = args.output.writer("blobshot", mode)?;
+    artifacts.write_receipt(&receipt)?;
+    artifacts.write_image_id(&BLOB_ID)?;
+
+    println!("Artifacts: {}", artifacts.output_dir().display());
+    Ok(())
+}
+
+/// Proves that the blob was published under the data root of the inputs
+pub fn prove(
+    blob_bytes: &[u8],
+    my_namespace: Namespace,
+    share_version: ShareVersion,
+    inputs: &InclusionInputs,
+) -> Result<Receipt> {
+    let mut data_root_tree = DataRootTree::from_dah(&inputs.dah)?;
+    let share_values =
+        stock0_celestia::split_blob_to_shares(my_namespace, share_version, blob_bytes)?;
+
+    // Calculate blob commitment
+    let blob_commitment = stock0_celestia::blob_commitment(my_namespace, &share_values)?;
+
+    println!(
+        "BLOB NAMESPACE: {}",
+        base64::engine::general_purpose::STANDARD.encode(my_namespace.as_bytes())
+    );
+    println!(
+        "BLOB COMMITMENT: {}",
+        base64::engine::general_purpose::STANDARD.encode(blob_commitment)
+    );
+
+    // For each row spanned by the blob, you should have one NMT range proof into a row root.
+    let locator = inputs.share_proofs.locate(
+        inputs.blob_index,
+        share_values.len(),
+        data_root_tree.ods_width(),
+    )?;
+
+    let rp = data_root_tree.build_row_range_proof(locator.rows());
+
+    // Write to stdin -----------------------
+
+    let mut env = ExecutorEnv::builder();
+
+    env.write_slice(&data_root_tree.data_root());
+
+    // write "num rows" spanned by the blob
+    env.write(&(locator.num_rows() as u32))?;
+    // write num shares
+    env.write(&(share_values.len() as u32))?;
+    // write namespace;
+    env.write_slice(my_namespace.as_bytes());
+    // write the range proof
+    env.write(&rp)?;
+
+    // write the row roots
+    for row_root in data_root_tree.row_roots()[locator.rows()].iter() {
+        env.write(&row_root)?;
+    }
+    // write the shares
+    for share in share_values {
+        env.write_slice(&share);
+    }
+
+    // write the proofs
+    for proof in &inputs.share_proofs.proofs {
+        env.write(proof)?;
+    }
+
+    // Generate proof --------------------------
+
+    let env = env.build()?;
+
+    let prover = default_prover();
+    let prove_info = prover.prove(env, BLOB_ELF)?;
+
+    // Check that everything is OK
+    prove_info.receipt.verify(BLOB_ID).context("failed to verify")?;
+    let journal = BlobshotJournal::from_bytes(&prove_info.receipt.journal.bytes)?;
+    ensure!(
+        journal.blob_commitment == blob_commitment,
+        "Committed blob commitment does not match the blob"
+    );
+    ensure!(
+        journal.namespace == my_namespace.as_bytes(),
+        "Committed namespace does not match"
+    );
+
+    Ok(prove_info.receipt)
+}
+
+#[cfg(test)]
+mod tests {
+    use stock0_core::MockSquareBuilder;
+
+    use super::*;
+
+    #[test]
+    fn proves_blob_past_first_row() {
+        // Executes the guest without proving
+        std::env::set_var("RISC0_DEV_MODE", "1");
+
+        let other = stock0_celestia::namespace_v0(&[1, 1]).unwrap();
+        let my_namespace = stock0_celestia::namespace_v0(&[1, 2, 3, 4, 5]).unwrap();
+        let blob_bytes: Vec<u8> = (0..6000).map(|i| (i % 251) as u8).collect();
+
+        // 11 shares of another namespace go first, the blob takes 13 shares of an 8x8 square
+        let mut builder = MockSquareBuilder::new();
+        let other_shares =
+            stock0_celestia::split_blob_to_shares(other, ShareVersion::V0, &[7u8; 5000]).unwrap();
+        builder.add_blob(other, other_shares).unwrap();
+        let shares =
+            stock0_celestia::split_blob_to_shares(my_namespace, ShareVersion::V0, &blob_bytes)
+                .unwrap();
+        let blob_id = builder.add_blob(my_namespace, shares).unwrap();
+        let square = builder.build().unwrap();
+
+        let inputs = square.inclusion_inputs(blob_id).unwrap();
+        assert_eq!(square.ods_width(), 8);
+        assert_eq!(inputs.blob_index, 11);
+
+        let receipt = prove(&blob_bytes, my_namespace, ShareVersion::V0, &inputs).unwrap();
+        let journal = BlobshotJournal::from_bytes(&receipt.journal.bytes).unwrap();
+        let data_root_tree = DataRootTree::from_dah(&inputs.dah).unwrap();
+        assert_eq!(journal.data_root, data_root_tree.data_root());
+
+        // Start of the first share proof is a column, the guest rejects the rows it points at
+        let mut misplaced = square.inclusion_inputs(blob_id).unwrap();
+        misplaced.blob_index = misplaced.share_proofs.start as usize;
+        assert!(prove(&blob_bytes, my_namespace, ShareVersion::V0, &misplaced).is_err());
+    }
+}
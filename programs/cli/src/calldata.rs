@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use stock0_core::aligned::{create_market_item_calldata, deliver_market_item_calldata};
+use stock0_core::{read_receipt, VerificationData};
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(subcommand)]
+    call: Call,
+}
+
+#[derive(Subcommand)]
+enum Call {
+    /// `createMarketItem`, offer an image backed by the thumbnail proof
+    Create {
+        /// Aligned verification data of the thumbnail proof (~/.aligned/aligned_verification_data/*.json)
+        #[arg(long)]
+        verification_data: PathBuf,
+
+        /// Image hash, hex
+        #[arg(long)]
+        image_hash: String,
+
+        /// Thumbnail hash, hex
+        #[arg(long)]
+        thumbnail_hash: String,
+
+        /// CID of the C2PA manifest, hex
+        #[arg(long)]
+        manifest_cid: String,
+
+        /// Price (wei)
+        #[arg(long)]
+        price: u128,
+    },
+    /// `deliverMarketItem`, release the payment with the delivery proof
+    Deliver {
+        /// Aligned verification data of the delivery proof
+        #[arg(long)]
+        verification_data: PathBuf,
+
+        /// Delivery receipt, the journal is passed to the contract
+        #[arg(long)]
+        receipt: PathBuf,
+
+        #[arg(long)]
+        item_id: u128,
+    },
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let calldata = match args.call {
+        Call::Create {
+            verification_data,
+            image_hash,
+            thumbnail_hash,
+            manifest_cid,
+            price,
+        } => create_market_item_calldata(
+            &decode_hex(&image_hash).context("Image hash is not a valid hex string")?,
+            &decode_hex(&thumbnail_hash).context("Thumbnail hash is not a valid hex string")?,
+            &decode_hex(&manifest_cid).context("Manifest CID is not a valid hex string")?,
+            price,
+            &load_verification_data(&verification_data)?,
+        ),
+        Call::Deliver {
+            verification_data,
+            receipt,
+            item_id,
+        } => {
+            let receipt = read_receipt(&receipt)?;
+            deliver_market_item_calldata(
+                item_id,
+                &receipt.journal.bytes,
+                &load_verification_data(&verification_data)?,
+            )?
+        }
+    };
+
+    println!("CALLDATA: 0x{}", hex::encode(calldata));
+    Ok(())
+}
+
+fn load_verification_data(path: &Path) -> Result<VerificationData> {
+    let data_str = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    VerificationData::from_json(&data_str)
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    Ok(hex::decode(value.trim_start_matches("0x"))?)
+}
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context, Result};
+use delivery_methods::DELIVERY_GEN_ID;
+use envelope_methods::ENVELOPE_GEN_ID;
+use stock0_core::read_receipt;
+use stock0_journal::{DeliveryJournal, EnvelopeJournal, Journal};
+
+use crate::keys::{self, KeyFileArgs};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Blob produced by envelope or delivery program
+    #[arg(long)]
+    blob: PathBuf,
+
+    /// Buyer's secret key (secp256k1 scalar), hex
+    #[arg(
+        long,
+        required_unless_present = "key_file",
+        conflicts_with = "key_file"
+    )]
+    secret_key: Option<String>,
+
+    /// Read the buyer's secret key from their key file
+    #[command(flatten)]
+    key_file: KeyFileArgs,
+
+    /// Envelope or delivery receipt, the image hash is taken from its (verified) journal
+    #[arg(long)]
+    receipt: PathBuf,
+
+    /// Where to write the decrypted image
+    #[arg(long, short)]
+    output: PathBuf,
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let secret_key = match &args.secret_key {
+        Some(secret_key_hex) => keys::parse_secret_key(secret_key_hex)?,
+        None => args.key_file.secret_key()?,
+    };
+
+    let blob = std::fs::read(&args.blob)
+        .with_context(|| format!("Failed to read {}", args.blob.display()))?;
+    let receipt = read_receipt(&args.receipt)?;
+
+    // Envelope and delivery journals differ in length, both carry the original image hash.
+    // It is only trusted once the receipt is verified against the image ID of its program.
+    let journal = &receipt.journal.bytes;
+    let image_hash = match EnvelopeJournal::from_bytes(journal) {
+        Ok(journal) => {
+            receipt
+                .verify(ENVELOPE_GEN_ID)
+                .context("Receipt is not a valid envelope proof")?;
+            journal.image_hash
+        }
+        Err(_) => {
+            let journal = DeliveryJournal::from_bytes(journal)
+                .context("Neither an envelope nor a delivery journal")?;
+            receipt
+                .verify(DELIVERY_GEN_ID)
+                .context("Receipt is not a valid delivery proof")?;
+            journal.image_hash
+        }
+    };
+
+    let (header, image_bytes) = stock0_crypto::open(&blob, &secret_key, &image_hash)?;
+    ensure!(
+        stock0_crypto::sha256(&image_bytes) == image_hash,
+        "Decrypted image does not match the committed image hash (wrong key or corrupted blob)"
+    );
+
+    std::fs::write(&args.output, &image_bytes)
+        .with_context(|| format!("Failed to write {}", args.output.display()))?;
+
+    println!("ENVELOPE VERSION: {}", header.version);
+    println!("MIME TYPE: {}", header.mime_type);
+    println!("IMAGE HASH: {}", hex::encode(image_hash));
+    println!("Decrypted image: {}", args.output.display());
+    Ok(())
+}
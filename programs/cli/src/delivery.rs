@@ -0,0 +1,286 @@
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context, Result};
+use base64::Engine;
+use celestia_types::nmt::Namespace;
+use delivery_methods::{DELIVERY_GEN_ELF, DELIVERY_GEN_ID};
+use risc0_zkvm::{default_prover, ExecutorEnv, Receipt};
+use stock0_celestia::ShareVersion;
+use stock0_core::{DataRootTree, InclusionInputs, MockSquareBuilder, Mode};
+use stock0_crypto::{CipherSuite, SealingKeys};
+use stock0_journal::{DeliveryJournal, Journal};
+
+use crate::keys::SealingArgs;
+use crate::{NamespaceArgs, OutputArgs, ShareArgs};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Original image
+    #[arg(long, short)]
+    input: PathBuf,
+
+    /// Extended header of the block containing the blob (protobuf)
+    #[arg(long, required_unless_present = "mock")]
+    header: Option<PathBuf>,
+
+    /// Share proofs as returned by `blob.GetProof` (JSON)
+    #[arg(long, required_unless_present = "mock")]
+    proofs: Option<PathBuf>,
+
+    /// Index of the first share of the blob in the original data square (row-major),
+    /// as returned by `blob.Get`
+    #[arg(long, required_unless_present = "mock")]
+    blob_index: Option<usize>,
+
+    /// Lay the blob out in an in-memory mock data square instead of loading the header and
+    /// the proofs of a block (offline runs, the data root is not the one of a real block)
+    #[arg(long, conflicts_with_all = ["header", "proofs", "blob_index"])]
+    mock: bool,
+
+    #[command(flatten)]
+    sealing: SealingArgs,
+
+    #[command(flatten)]
+    namespace: NamespaceArgs,
+
+    #[command(flatten)]
+    share: ShareArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+pub fn run(args: Args, mode: Mode) -> Result<()> {
+    // Buyer's public key, session key and ElGamal randomness (private inputs)
+    let keys = args.sealing.sealing_keys()?;
+    let cipher_suite = args.sealing.cipher_suite();
+
+    // Source image (private input)
+    let image_bytes = std::fs::read(&args.input)
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+
+    // Create namespace
+    let my_namespace = args.namespace.namespace()?;
+    let share_version = args.share.share_version()?;
+
+    // Load header and share proofs ---------
+
+    let inputs = match (args.header, args.proofs, args.blob_index) {
+        (Some(header), Some(proofs), Some(blob_index)) => {
+            InclusionInputs::load(&header, &proofs, blob_index)?
+        }
+        _ => {
+            // Sealing is deterministic for the given keys, prove seals the same blob again
+            let image_hash = stock0_crypto::sha256(&image_bytes);
+            let mime_type = stock0_crypto::sniff_mime_type(&image_bytes);
+            let blob_data =
+                stock0_crypto::seal(&image_bytes, &image_hash, mime_type, cipher_suite, &keys)?;
+            mock_inclusion_inputs(&blob_data, my_namespace, share_version)?
+        }
+    };
+
+    let (receipt, blob_data) = prove(
+        &image_bytes,
+        &keys,
+        cipher_suite,
+        my_namespace,
+        share_version,
+        &inputs,
+    )?;
+    let journal = DeliveryJournal::from_bytes(&receipt.journal.bytes)?;
+
+    let artifacts = args.output.writer("delivery", mode)?;
+    artifacts.write_receipt(&receipt)?;
+    artifacts.write_image_id(&DELIVERY_GEN_ID)?;
+    artifacts.write("blob", &blob_data)?;
+
+    println!("BUYER ADDRESS: 0x{}", hex::encode(journal.buyer_address));
+    println!("Artifacts: {}", artifacts.output_dir().display());
+    Ok(())
+}
+
+/// Seals the image for the buyer and proves that the sealed blob was published
+/// under the data root of the inputs. Returns the receipt and the blob.
+pub fn prove(
+    image_bytes: &[u8],
+    keys: &SealingKeys,
+    cipher_suite: CipherSuite,
+    my_namespace: Namespace,
+    share_version: ShareVersion,
+    inputs: &InclusionInputs,
+) -> Result<(Receipt, Vec<u8>)> {
+    // Create blob
+    let image_hash = stock0_crypto::sha256(image_bytes);
+    let mime_type = stock0_crypto::sniff_mime_type(image_bytes);
+    let blob_data = stock0_crypto::seal(image_bytes, &image_hash, mime_type, cipher_suite, keys)?;
+
+    let mut data_root_tree = DataRootTree::from_dah(&inputs.dah)?;
+
+    // Load blob ----------------------------
+
+    let shares = stock0_celestia::split_blob_to_shares(my_namespace, share_version, &blob_data)?;
+
+    // Calculate blob commitment
+    let blob_commitment = stock0_celestia::blob_commitment(my_namespace, &shares)?;
+
+    println!(
+        "BLOB NAMESPACE: {}",
+        base64::engine::general_purpose::STANDARD.encode(my_namespace.as_bytes())
+    );
+    println!(
+        "BLOB COMMITMENT: {}",
+        base64::engine::general_purpose::STANDARD.encode(blob_commitment)
+    );
+
+    // For each row spanned by the blob, you should have one NMT range proof into a row root.
+    let locator =
+        inputs
+            .share_proofs
+            .locate(inputs.blob_index, shares.len(), data_root_tree.ods_width())?;
+
+    let rp = data_root_tree.build_row_range_proof(locator.rows());
+
+    // Write to stdin -----------------------
+
+    let mut env = ExecutorEnv::builder();
+    let num_rows = locator.num_rows() as u32;
+
+    // write data root
+    env.write_slice(&data_root_tree.data_root());
+    // write "num rows" spanned by the blob
+    env.write(&num_rows)?;
+    // write the range proof
+    env.write(&rp)?;
+    // write the row roots
+    for row_root in data_root_tree.row_roots()[locator.rows()].iter() {
+        env.write(&row_root)?;
+    }
+    // write the proofs
+    for proof in &inputs.share_proofs.proofs {
+        env.write(proof)?;
+    }
+    // write encryption data
+    env.write_slice(&keys.public_key_h);
+    env.write_slice(&keys.session_scalar);
+    env.write_slice(&keys.ephemeral_scalar);
+    env.write_slice(&keys.delivery_nonce);
+    env.write_slice(&[cipher_suite as u8]);
+    env.write_slice(my_namespace.as_bytes());
+    env.write_slice(&[share_version.as_u8()]);
+    env.write_slice(&share_version.signer());
+    // the image is streamed by the guest, its length and hash go first
+    env.write_slice(&(image_bytes.len() as u64).to_be_bytes());
+    env.write_slice(&image_hash);
+    env.write_slice(image_bytes);
+
+    // Generate proof --------------------------
+
+    let env = env.build()?;
+
+    let prover = default_prover();
+    let prove_info = prover.prove(env, DELIVERY_GEN_ELF)?;
+
+    // Check that everything is OK
+    prove_info.receipt.verify(DELIVERY_GEN_ID).context("failed to verify")?;
+    let journal = DeliveryJournal::from_bytes(&prove_info.receipt.journal.bytes)?;
+    ensure!(
+        journal.data_root == data_root_tree.data_root(),
+        "Committed data root does not match the header"
+    );
+    ensure!(
+        journal.blob_commitment == blob_commitment,
+        "Committed blob commitment does not match the blob"
+    );
+    ensure!(
+        journal.namespace == my_namespace.as_bytes(),
+        "Committed namespace does not match"
+    );
+    ensure!(
+        journal.share_version == share_version.as_u8() && journal.signer == share_version.signer(),
+        "Committed share version does not match"
+    );
+
+    Ok((prove_info.receipt, blob_data))
+}
+
+/// Publishes the blob alone in a mock data square
+fn mock_inclusion_inputs(
+    blob_data: &[u8],
+    my_namespace: Namespace,
+    share_version: ShareVersion,
+) -> Result<InclusionInputs> {
+    let shares = stock0_celestia::split_blob_to_shares(my_namespace, share_version, blob_data)?;
+    let mut builder = MockSquareBuilder::new();
+    let blob_id = builder.add_blob(my_namespace, shares)?;
+    builder.build()?.inclusion_inputs(blob_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::elliptic_curve::sec1::ToEncodedPoint;
+
+    use super::*;
+
+    #[test]
+    fn proves_delivery_past_first_row() {
+        // Executes the guest without proving
+        std::env::set_var("RISC0_DEV_MODE", "1");
+
+        let secret_key = [0x11; 32];
+        let public_key = k256::SecretKey::from_slice(&secret_key)
+            .unwrap()
+            .public_key()
+            .to_encoded_point(true);
+        let keys = SealingKeys {
+            public_key_h: public_key.as_bytes().try_into().unwrap(),
+            session_scalar: [0x22; 32],
+            ephemeral_scalar: [0x33; 32],
+            delivery_nonce: [0x44; 12],
+        };
+        let cipher_suite = CipherSuite::ChaCha20Poly1305;
+        let image_bytes: Vec<u8> = (0..3000).map(|i| (i % 251) as u8).collect();
+        let my_namespace = stock0_celestia::namespace_v0(&[1, 2, 3, 4, 5]).unwrap();
+
+        // The blob is sealed the same way by the host, so the mock square can be built upfront
+        let image_hash = stock0_crypto::sha256(&image_bytes);
+        let mime_type = stock0_crypto::sniff_mime_type(&image_bytes);
+        let blob_data =
+            stock0_crypto::seal(&image_bytes, &image_hash, mime_type, cipher_suite, &keys).unwrap();
+
+        // 11 shares of another namespace go first, the blob spans two rows of an 8x8 square
+        let other = stock0_celestia::namespace_v0(&[1, 1]).unwrap();
+        let mut builder = MockSquareBuilder::new();
+        let other_shares =
+            stock0_celestia::split_blob_to_shares(other, ShareVersion::V0, &[7u8; 5000]).unwrap();
+        builder.add_blob(other, other_shares).unwrap();
+        let shares =
+            stock0_celestia::split_blob_to_shares(my_namespace, ShareVersion::V0, &blob_data)
+                .unwrap();
+        let blob_id = builder.add_blob(my_namespace, shares).unwrap();
+        let square = builder.build().unwrap();
+
+        let inputs = square.inclusion_inputs(blob_id).unwrap();
+        assert_eq!(square.ods_width(), 8);
+        assert_eq!(inputs.blob_index, 11);
+
+        let (receipt, blob) = prove(
+            &image_bytes,
+            &keys,
+            cipher_suite,
+            my_namespace,
+            ShareVersion::V0,
+            &inputs,
+        )
+        .unwrap();
+        assert_eq!(blob, blob_data);
+
+        let journal = DeliveryJournal::from_bytes(&receipt.journal.bytes).unwrap();
+        assert_eq!(
+            journal.buyer_address,
+            stock0_crypto::ethereum_address(&keys.public_key_h).unwrap()
+        );
+
+        let (_, opened) = stock0_crypto::open(&blob, &secret_key, &journal.image_hash).unwrap();
+        assert_eq!(opened, image_bytes);
+    }
+}
@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context, Result};
+use envelope_methods::{ENVELOPE_GEN_ELF, ENVELOPE_GEN_ID};
+use risc0_zkvm::{default_prover, ExecutorEnv};
+use stock0_core::Mode;
+use stock0_journal::{EnvelopeJournal, Journal};
+
+use crate::keys::SealingArgs;
+use crate::{NamespaceArgs, OutputArgs, ShareArgs};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Original image
+    #[arg(long, short)]
+    input: PathBuf,
+
+    #[command(flatten)]
+    sealing: SealingArgs,
+
+    #[command(flatten)]
+    namespace: NamespaceArgs,
+
+    #[command(flatten)]
+    share: ShareArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+pub fn run(args: Args, mode: Mode) -> Result<()> {
+    let keys = args.sealing.sealing_keys()?;
+    let cipher_suite = args.sealing.cipher_suite();
+    let namespace = args.namespace.namespace()?;
+    let share_version = args.share.share_version()?;
+
+    let image_bytes = std::fs::read(&args.input)
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+    let mut blob = Vec::new();
+
+    let env = ExecutorEnv::builder()
+        .write_slice(&keys.public_key_h)
+        .write_slice(&keys.session_scalar)
+        .write_slice(&keys.ephemeral_scalar)
+        .write_slice(&keys.delivery_nonce)
+        .write_slice(&[cipher_suite as u8])
+        .write_slice(namespace.as_bytes())
+        .write_slice(&[share_version.as_u8()])
+        .write_slice(&share_version.signer())
+        .write_slice(&image_bytes)
+        .stdout(&mut blob)
+        .build()?;
+
+    let prover = default_prover();
+    let prove_info = prover.prove(env, ENVELOPE_GEN_ELF)?;
+
+    // Check that everything is OK
+    prove_info.receipt.verify(ENVELOPE_GEN_ID).context("failed to verify")?;
+    let journal = EnvelopeJournal::from_bytes(&prove_info.receipt.journal.bytes)?;
+    ensure!(
+        journal.public_key_h == keys.public_key_h
+            && journal.delivery_nonce == keys.delivery_nonce
+            && journal.namespace == namespace.as_bytes()
+            && journal.share_version == share_version.as_u8()
+            && journal.signer == share_version.signer(),
+        "Journal does not match the sealing inputs"
+    );
+
+    let artifacts = args.output.writer("envelope", mode)?;
+    artifacts.write_receipt(&prove_info.receipt)?;
+    artifacts.write_image_id(&ENVELOPE_GEN_ID)?;
+    artifacts.write("blob", &blob)?;
+
+    println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+    println!("BLOB COMMITMENT: {}", hex::encode(journal.blob_commitment));
+    println!("BUYER ADDRESS: 0x{}", hex::encode(journal.buyer_address));
+    println!("Artifacts: {}", artifacts.output_dir().display());
+    Ok(())
+}
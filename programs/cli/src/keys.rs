@@ -0,0 +1,195 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use k256::{
+    elliptic_curve::{
+        rand_core::{OsRng, RngCore},
+        sec1::ToEncodedPoint,
+        Field, PrimeField, PublicKey,
+    },
+    Scalar, Secp256k1,
+};
+use stock0_crypto::{CipherSuite, SealingKeys, NONCE_SIZE};
+
+/// Symmetric cipher used to encrypt the content
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum Cipher {
+    /// Bare stream cipher
+    Chacha20,
+    /// Authenticated encryption, lets the buyer detect a tampered blob
+    Chacha20Poly1305,
+}
+
+impl From<Cipher> for CipherSuite {
+    fn from(cipher: Cipher) -> Self {
+        match cipher {
+            Cipher::Chacha20 => CipherSuite::ChaCha20,
+            Cipher::Chacha20Poly1305 => CipherSuite::ChaCha20Poly1305,
+        }
+    }
+}
+
+/// Key material for the envelope encryption.
+/// In order to reproduce a published blob, all of them have to be the same.
+#[derive(clap::Args)]
+pub struct SealingArgs {
+    /// Buyer's public key, hex: SEC1 (compressed or uncompressed) or raw 64-byte `x || y`
+    #[arg(
+        long,
+        required_unless_present = "public_key_file",
+        conflicts_with = "public_key_file"
+    )]
+    public_key: Option<String>,
+
+    /// Read the buyer's public key (same formats as `--public-key`) from a file
+    #[arg(long)]
+    public_key_file: Option<PathBuf>,
+
+    /// Session key (secp256k1 scalar), hex [default: random]
+    #[arg(long)]
+    session_key: Option<String>,
+
+    /// Ephemeral ElGamal key (secp256k1 scalar), hex [default: random]
+    #[arg(long)]
+    ephemeral_key: Option<String>,
+
+    /// Per-delivery nonce (12 bytes), hex [default: random]
+    #[arg(long)]
+    nonce: Option<String>,
+
+    /// Content cipher
+    #[arg(long, value_enum, default_value = "chacha20")]
+    cipher: Cipher,
+}
+
+impl SealingArgs {
+    pub fn cipher_suite(&self) -> CipherSuite {
+        self.cipher.into()
+    }
+
+    pub fn sealing_keys(&self) -> Result<SealingKeys> {
+        let public_key_h = match (&self.public_key, &self.public_key_file) {
+            (Some(public_key_hex), _) => parse_public_key(public_key_hex)?,
+            (None, Some(path)) => {
+                let contents = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                parse_public_key(contents.trim())?
+            }
+            (None, None) => bail!("Buyer's public key is not specified"),
+        };
+        Ok(SealingKeys {
+            public_key_h,
+            session_scalar: scalar_or_random(self.session_key.as_deref())?,
+            ephemeral_scalar: scalar_or_random(self.ephemeral_key.as_deref())?,
+            delivery_nonce: nonce_or_random(self.nonce.as_deref())?,
+        })
+    }
+}
+
+/// Ethereum-style key file: a hex encoded private key or a V3 (JSON) keystore
+#[derive(clap::Args)]
+pub struct KeyFileArgs {
+    /// Key file, either a hex private key or an Ethereum V3 keystore
+    #[arg(long)]
+    key_file: Option<PathBuf>,
+
+    /// Keystore password
+    #[arg(long, env = "STOCK0_KEY_PASSWORD", hide_env_values = true)]
+    key_password: Option<String>,
+}
+
+impl KeyFileArgs {
+    /// Secret key (big endian) stored in the key file
+    pub fn secret_key(&self) -> Result<[u8; 32]> {
+        let path = self
+            .key_file
+            .as_ref()
+            .context("Key file is not specified")?;
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        if contents.trim_start().starts_with('{') {
+            let password = self
+                .key_password
+                .as_ref()
+                .context("Keystore password is required (--key-password)")?;
+            let secret_key = eth_keystore::decrypt_key(path, password)
+                .map_err(|err| anyhow::anyhow!("Failed to decrypt keystore: {err}"))?;
+            let secret_key: [u8; 32] = secret_key
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Keystore must contain a 32-byte key"))?;
+            Ok(parse_scalar_bytes(secret_key)?.to_bytes().into())
+        } else {
+            parse_secret_key(contents.trim())
+        }
+    }
+}
+
+/// Buyer's public key in compressed SEC1 form (33 bytes).
+/// Accepts compressed or uncompressed SEC1 keys, as well as uncompressed keys
+/// without the `04` prefix (as exported by Ethereum tooling).
+pub fn parse_public_key(public_key_hex: &str) -> Result<[u8; 33]> {
+    let mut public_key_bytes = hex::decode(public_key_hex.trim_start_matches("0x"))
+        .context("Public key is not a valid hex string")?;
+    if public_key_bytes.len() == 64 {
+        public_key_bytes.insert(0, 0x04);
+    }
+    let public_key =
+        PublicKey::<Secp256k1>::from_sec1_bytes(&public_key_bytes).context("Invalid public key")?;
+    Ok(compressed(&public_key))
+}
+
+fn compressed(public_key: &PublicKey<Secp256k1>) -> [u8; 33] {
+    public_key
+        .to_encoded_point(true)
+        .as_bytes()
+        .try_into()
+        .expect("compressed SEC1 point is 33 bytes")
+}
+
+/// Scalar used as a session or ephemeral key (32 bytes, big endian).
+/// If not specified, a random one is generated.
+pub fn scalar_or_random(scalar_hex: Option<&str>) -> Result<[u8; 32]> {
+    let scalar = match scalar_hex {
+        Some(scalar_hex) => parse_scalar(scalar_hex)?,
+        None => Scalar::random(&mut OsRng),
+    };
+    Ok(scalar.to_bytes().into())
+}
+
+/// Per-delivery nonce, must be unique for every sale.
+/// If not specified, a random one is generated.
+pub fn nonce_or_random(nonce_hex: Option<&str>) -> Result<[u8; NONCE_SIZE]> {
+    match nonce_hex {
+        Some(nonce_hex) => hex::decode(nonce_hex.trim_start_matches("0x"))
+            .context("Nonce is not a valid hex string")?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Nonce must be {NONCE_SIZE} bytes long")),
+        None => {
+            let mut nonce = [0u8; NONCE_SIZE];
+            OsRng.fill_bytes(&mut nonce);
+            Ok(nonce)
+        }
+    }
+}
+
+/// Secret key (secp256k1 scalar) in hex, returns its big endian bytes
+pub fn parse_secret_key(secret_key_hex: &str) -> Result<[u8; 32]> {
+    Ok(parse_scalar(secret_key_hex)?.to_bytes().into())
+}
+
+fn parse_scalar(scalar_hex: &str) -> Result<Scalar> {
+    let scalar_bytes: [u8; 32] = hex::decode(scalar_hex.trim_start_matches("0x"))
+        .context("Key is not a valid hex string")?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Key must be 32 bytes long"))?;
+    parse_scalar_bytes(scalar_bytes)
+}
+
+fn parse_scalar_bytes(scalar_bytes: [u8; 32]) -> Result<Scalar> {
+    let scalar: Option<Scalar> = Scalar::from_repr(scalar_bytes.into()).into();
+    match scalar {
+        Some(scalar) if !bool::from(scalar.is_zero()) => Ok(scalar),
+        _ => bail!("Key is not a valid scalar"),
+    }
+}
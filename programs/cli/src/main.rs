@@ -0,0 +1,166 @@
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context, Result};
+use celestia_types::nmt::Namespace;
+use clap::{Parser, Subcommand};
+use stock0_celestia::{ShareVersion, SIGNER_SIZE};
+use stock0_core::{ArtifactWriter, Mode};
+
+mod attest;
+mod bench;
+mod blobshot;
+mod calldata;
+mod decrypt;
+mod delivery;
+mod envelope;
+mod keys;
+mod pipeline;
+mod preview;
+mod submit;
+mod thumbnail;
+mod trade;
+mod verify;
+mod watermark;
+
+/// Stock0 proving pipeline
+#[derive(Parser)]
+#[command(name = "stock0", version)]
+struct Cli {
+    /// Execute programs without generating proofs (same as RISC0_DEV_MODE=1)
+    #[arg(long, global = true)]
+    dev: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a thumbnail and prove it was derived from the original image
+    Thumbnail(thumbnail::Args),
+    /// Measure cycle counts of the thumbnail program per image
+    Bench(bench::Args),
+    /// Overlay a watermark on an image and prove the output was derived from the original
+    Watermark(watermark::Args),
+    /// Blur or pixelate an image and prove the preview was derived from the original
+    Preview(preview::Args),
+    /// Apply an ordered list of operations to an image with a single proof
+    Pipeline(pipeline::Args),
+    /// Encrypt an image for the buyer and compute the blob commitment
+    Envelope(envelope::Args),
+    /// Prove inclusion of a blob into a Celestia block
+    Blobshot(blobshot::Args),
+    /// Publish a blob to Celestia and fetch its header and share proofs
+    Submit(submit::Args),
+    /// Encrypt an image for the buyer and prove the blob was published to Celestia
+    Delivery(delivery::Args),
+    /// Build the Blobstream attestation proof of the block a blob was delivered in
+    Attest(attest::Args),
+    /// Prove that the thumbnail and delivery receipts refer to the same image (single receipt)
+    Trade(trade::Args),
+    /// Decrypt a delivered blob with the buyer's secret key
+    Decrypt(decrypt::Args),
+    /// Verify a receipt against the known image IDs and decode its journal
+    Verify(verify::Args),
+    /// Encode a `Market` contract call from the Aligned verification data
+    Calldata(calldata::Args),
+}
+
+/// Where to store the artifacts
+#[derive(clap::Args)]
+pub struct OutputArgs {
+    /// Output directory [default: target/<dev / prod>/<program name>]
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+impl OutputArgs {
+    pub fn writer(&self, program: &str, mode: Mode) -> Result<ArtifactWriter> {
+        match &self.output {
+            Some(output_dir) => ArtifactWriter::new(output_dir),
+            None => ArtifactWriter::for_program(program, mode),
+        }
+    }
+}
+
+/// Celestia namespace of the blob, must be the same for all programs of a deployment
+#[derive(clap::Args)]
+pub struct NamespaceArgs {
+    /// Namespace ID (version 0, up to 10 bytes), hex
+    #[arg(long, default_value = "0102030405")]
+    namespace: String,
+}
+
+impl NamespaceArgs {
+    pub fn namespace(&self) -> Result<Namespace> {
+        let id = hex::decode(self.namespace.trim_start_matches("0x"))
+            .context("Namespace is not a valid hex string")?;
+        Ok(stock0_celestia::namespace_v0(&id)?)
+    }
+}
+
+/// Celestia share version of the blob
+#[derive(clap::Args)]
+pub struct ShareArgs {
+    /// Address of the account that pays for the blob, bech32 (`celestia1...`) or hex.
+    /// Switches to share version 1, so that the proof binds the blob to its signer
+    #[arg(long)]
+    signer: Option<String>,
+}
+
+/// Human readable part of Celestia account addresses
+const CELESTIA_HRP: &str = "celestia";
+
+impl ShareArgs {
+    pub fn share_version(&self) -> Result<ShareVersion> {
+        let Some(signer) = &self.signer else {
+            return Ok(ShareVersion::V0);
+        };
+        let signer = if signer.starts_with(CELESTIA_HRP) {
+            let (hrp, signer) =
+                bech32::decode(signer).context("Signer is not a valid bech32 address")?;
+            ensure!(
+                hrp.as_str() == CELESTIA_HRP,
+                "Signer must be a {CELESTIA_HRP}1... account address"
+            );
+            signer
+        } else {
+            hex::decode(signer.trim_start_matches("0x"))
+                .context("Signer is neither a bech32 address nor a hex string")?
+        };
+        let signer: [u8; SIGNER_SIZE] = signer
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Signer must be {SIGNER_SIZE} bytes long"))?;
+        Ok(ShareVersion::V1 { signer })
+    }
+}
+
+fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::filter::EnvFilter::from_default_env())
+        .init();
+
+    let cli = Cli::parse();
+    if cli.dev {
+        // Prover picks the mode from the environment
+        std::env::set_var("RISC0_DEV_MODE", "1");
+    }
+    let mode = Mode::from_env();
+
+    match cli.command {
+        Command::Thumbnail(args) => thumbnail::run(args, mode),
+        Command::Bench(args) => bench::run(args),
+        Command::Watermark(args) => watermark::run(args, mode),
+        Command::Preview(args) => preview::run(args, mode),
+        Command::Pipeline(args) => pipeline::run(args, mode),
+        Command::Envelope(args) => envelope::run(args, mode),
+        Command::Blobshot(args) => blobshot::run(args, mode),
+        Command::Submit(args) => submit::run(args, mode),
+        Command::Delivery(args) => delivery::run(args, mode),
+        Command::Attest(args) => attest::run(args, mode),
+        Command::Trade(args) => trade::run(args, mode),
+        Command::Decrypt(args) => decrypt::run(args),
+        Command::Verify(args) => verify::run(args),
+        Command::Calldata(args) => calldata::run(args),
+    }
+}
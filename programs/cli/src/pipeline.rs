@@ -0,0 +1,162 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use clap::ValueEnum;
+use pipeline_methods::{PIPELINE_GEN_ELF, PIPELINE_GEN_ID};
+use risc0_zkvm::{default_prover, ExecutorEnv};
+use stock0_core::Mode;
+use stock0_format::ImageFormat;
+use stock0_spec::{ColorMode, Operation, Pipeline, ResizeFilter, Rotation, WatermarkKind};
+use stock0_journal::{Journal, PipelineJournal};
+
+use crate::{thumbnail, watermark, OutputArgs};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Original image (TIFF, PNG or JPEG)
+    #[arg(long, short)]
+    input: PathBuf,
+
+    /// Operation, applied in the order given: crop=x,y,width,height,
+    /// resize=width,height[,filter], rotate=90|180|270, color=grayscale|rgb,
+    /// watermark-text=<text>, watermark-image=<path>
+    #[arg(long = "op", required = true)]
+    operations: Vec<String>,
+
+    /// Placement and appearance of the watermark operations
+    #[command(flatten)]
+    watermark: watermark::SpecArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+impl Args {
+    fn pipeline(&self) -> Result<Pipeline> {
+        let operations = self
+            .operations
+            .iter()
+            .map(|op| {
+                self.operation(op)
+                    .with_context(|| format!("Invalid operation {op}"))
+            })
+            .collect::<Result<_>>()?;
+
+        let pipeline = Pipeline { operations };
+        pipeline.validate()?;
+        Ok(pipeline)
+    }
+
+    fn operation(&self, op: &str) -> Result<Operation> {
+        let (name, value) = op.split_once('=').context("Expected <operation>=<value>")?;
+        let numbers = || -> Result<Vec<u32>> {
+            value
+                .split(',')
+                .map(|number| number.parse().context("Expected a number"))
+                .collect()
+        };
+
+        let operation = match name {
+            "crop" => match numbers()?[..] {
+                [x, y, width, height] => Operation::Crop {
+                    x,
+                    y,
+                    width,
+                    height,
+                },
+                _ => bail!("Crop rectangle must be x,y,width,height"),
+            },
+            "resize" => {
+                let (size, filter) = match value.split(',').collect::<Vec<_>>()[..] {
+                    [width, height] => ([width, height], ResizeFilter::Box),
+                    [width, height, filter] => {
+                        let filter = thumbnail::Filter::from_str(filter, true)
+                            .map_err(|err| anyhow!(err))?;
+                        ([width, height], filter.into())
+                    }
+                    _ => bail!("Resize target must be width,height[,filter]"),
+                };
+                Operation::Resize {
+                    width: size[0].parse().context("Expected a number")?,
+                    height: size[1].parse().context("Expected a number")?,
+                    filter,
+                }
+            }
+            "rotate" => Operation::Rotate(match value {
+                "90" => Rotation::Rotate90,
+                "180" => Rotation::Rotate180,
+                "270" => Rotation::Rotate270,
+                _ => bail!("Rotation must be 90, 180 or 270 degrees"),
+            }),
+            "color" => Operation::ColorConvert(match value {
+                "grayscale" => ColorMode::Grayscale,
+                "rgb" => ColorMode::Rgb,
+                _ => bail!("Color mode must be grayscale or rgb"),
+            }),
+            "watermark-text" => {
+                ensure!(
+                    !value.is_empty() && !value.chars().any(char::is_control),
+                    "Watermark text must be a non-empty single line"
+                );
+                Operation::Watermark {
+                    spec: self.watermark.spec(WatermarkKind::Text)?,
+                    asset: value.as_bytes().to_vec(),
+                }
+            }
+            "watermark-image" => {
+                let asset =
+                    std::fs::read(value).with_context(|| format!("Failed to read {value}"))?;
+                ImageFormat::sniff(&asset)
+                    .context("Unsupported watermark format, expected TIFF, PNG or JPEG")?;
+                Operation::Watermark {
+                    spec: self.watermark.spec(WatermarkKind::Image)?,
+                    asset,
+                }
+            }
+            _ => bail!("Unknown operation {name}"),
+        };
+        operation.validate()?;
+        Ok(operation)
+    }
+}
+
+pub fn run(args: Args, mode: Mode) -> Result<()> {
+    let image_bytes = std::fs::read(&args.input)
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+    ImageFormat::sniff(&image_bytes)
+        .context("Unsupported image format, expected TIFF, PNG or JPEG")?;
+    let pipeline = args.pipeline()?;
+    let pipeline_bytes = pipeline.to_bytes();
+    let mut output_bytes = Vec::new();
+
+    let env = ExecutorEnv::builder()
+        .write(&(pipeline_bytes.len() as u32))?
+        .write_slice(&pipeline_bytes)
+        .write_slice(&image_bytes)
+        .stdout(&mut output_bytes)
+        .build()?;
+
+    let prover = default_prover();
+    let prove_info = prover.prove(env, PIPELINE_GEN_ELF)?;
+
+    // Check that everything is OK
+    prove_info.receipt.verify(PIPELINE_GEN_ID).context("failed to verify")?;
+    let journal = PipelineJournal::from_bytes(&prove_info.receipt.journal.bytes)?;
+    ensure!(
+        journal.pipeline_hash == stock0_crypto::sha256(&pipeline_bytes),
+        "Committed operation list does not match the input"
+    );
+
+    let artifacts = args.output.writer("pipeline", mode)?;
+    artifacts.write_receipt(&prove_info.receipt)?;
+    artifacts.write_image_id(&PIPELINE_GEN_ID)?;
+    artifacts.write("pipeline", &pipeline_bytes)?;
+    artifacts.write("output.png", &output_bytes)?;
+
+    println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+    println!("PIPELINE HASH: {}", hex::encode(journal.pipeline_hash));
+    println!("OUTPUT HASH: {}", hex::encode(journal.output_hash));
+    println!("PIPELINE: {pipeline}");
+    println!("Artifacts: {}", artifacts.output_dir().display());
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context, Result};
+use clap::ValueEnum;
+use preview_methods::{PREVIEW_GEN_ELF, PREVIEW_GEN_ID};
+use risc0_zkvm::{default_prover, ExecutorEnv};
+use stock0_core::Mode;
+use stock0_format::ImageFormat;
+use stock0_spec::{PreviewEffect, PreviewParams};
+use stock0_journal::{Journal, PreviewJournal};
+
+use crate::OutputArgs;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Original image (TIFF, PNG or JPEG)
+    #[arg(long, short)]
+    input: PathBuf,
+
+    /// Effect removing the details of the image
+    #[arg(long, value_enum, default_value_t = Effect::Blur)]
+    effect: Effect,
+
+    /// Standard deviation of the blur (1-64) or size of the pixelation blocks (2-1024), px
+    #[arg(long, default_value_t = 8)]
+    strength: u32,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Effect {
+    Blur,
+    Pixelate,
+}
+
+pub fn run(args: Args, mode: Mode) -> Result<()> {
+    let image_bytes = std::fs::read(&args.input)
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+    ImageFormat::sniff(&image_bytes)
+        .context("Unsupported image format, expected TIFF, PNG or JPEG")?;
+    let params = PreviewParams {
+        effect: match args.effect {
+            Effect::Blur => PreviewEffect::Blur,
+            Effect::Pixelate => PreviewEffect::Pixelate,
+        },
+        strength: args.strength,
+    };
+    params.validate()?;
+    let mut preview_bytes = Vec::new();
+
+    let env = ExecutorEnv::builder()
+        .write_slice(&params.to_bytes())
+        .write_slice(&image_bytes)
+        .stdout(&mut preview_bytes)
+        .build()?;
+
+    let prover = default_prover();
+    let prove_info = prover.prove(env, PREVIEW_GEN_ELF)?;
+
+    // Check that everything is OK
+    prove_info.receipt.verify(PREVIEW_GEN_ID).context("failed to verify")?;
+    let journal = PreviewJournal::from_bytes(&prove_info.receipt.journal.bytes)?;
+    ensure!(
+        journal.params_hash == stock0_crypto::sha256(&params.to_bytes()),
+        "Committed preview params do not match the input"
+    );
+
+    let artifacts = args.output.writer("preview", mode)?;
+    artifacts.write_receipt(&prove_info.receipt)?;
+    artifacts.write_image_id(&PREVIEW_GEN_ID)?;
+    artifacts.write("params", &params.to_bytes())?;
+    artifacts.write("preview.png", &preview_bytes)?;
+
+    println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+    println!("PARAMS HASH: {}", hex::encode(journal.params_hash));
+    println!("PREVIEW HASH: {}", hex::encode(journal.preview_hash));
+    println!("PREVIEW PARAMS: {params}");
+    println!("Artifacts: {}", artifacts.output_dir().display());
+    Ok(())
+}
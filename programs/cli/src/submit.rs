@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use stock0_core::rpc::{BLOB_INDEX_FILE, DEFAULT_RPC_URL, HEADER_FILE, PROOFS_FILE};
+use stock0_core::{CelestiaClient, Mode};
+
+use crate::{NamespaceArgs, OutputArgs, ShareArgs};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Blob data, e.g. target/prod/envelope/blob
+    #[arg(long)]
+    blob: PathBuf,
+
+    /// celestia-node JSON-RPC endpoint
+    #[arg(long, default_value = DEFAULT_RPC_URL)]
+    rpc_url: String,
+
+    /// Node auth token with write access (`celestia light auth admin`)
+    #[arg(long, env = "CELESTIA_NODE_AUTH_TOKEN", hide_env_values = true)]
+    auth_token: Option<String>,
+
+    /// Gas price (utia)
+    #[arg(long, default_value_t = 0.1)]
+    gas_price: f64,
+
+    /// Height of the block the blob is already included in, skips the submission
+    #[arg(long)]
+    height: Option<u64>,
+
+    #[command(flatten)]
+    namespace: NamespaceArgs,
+
+    #[command(flatten)]
+    share: ShareArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+pub fn run(args: Args, mode: Mode) -> Result<()> {
+    let namespace = args.namespace.namespace()?;
+    let share_version = args.share.share_version()?;
+    let blob_bytes = std::fs::read(&args.blob)
+        .with_context(|| format!("Failed to read {}", args.blob.display()))?;
+
+    let client = CelestiaClient::new(&args.rpc_url, args.auth_token);
+
+    let shares = stock0_celestia::split_blob_to_shares(namespace, share_version, &blob_bytes)?;
+    let commitment = stock0_celestia::blob_commitment(namespace, &shares)?;
+    let height = match args.height {
+        Some(height) => height,
+        None => {
+            client
+                .submit_blob(namespace, share_version, &blob_bytes, args.gas_price)?
+                .height
+        }
+    };
+
+    println!("HEIGHT: {height}");
+    println!(
+        "BLOB NAMESPACE: {}",
+        base64::engine::general_purpose::STANDARD.encode(namespace.as_bytes())
+    );
+    println!(
+        "BLOB COMMITMENT: {}",
+        base64::engine::general_purpose::STANDARD.encode(commitment)
+    );
+
+    // Inclusion inputs of blobshot and delivery
+    let artifacts = args.output.writer("celestia", mode)?;
+    let blob_index =
+        client.fetch_inclusion_inputs(height, namespace, &commitment, artifacts.output_dir())?;
+
+    println!("BLOB INDEX: {blob_index}");
+    println!(
+        "Artifacts: {} ({HEADER_FILE}, {PROOFS_FILE}, {BLOB_INDEX_FILE})",
+        artifacts.output_dir().display()
+    );
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, ensure, Context, Result};
+use clap::ValueEnum;
+use risc0_zkvm::{default_prover, ExecutorEnv};
+use stock0_core::Mode;
+use stock0_format::ImageFormat;
+use stock0_spec::{CropMode, ResizeFilter, ThumbnailFormat, ThumbnailSpec};
+use stock0_journal::{Journal, ThumbnailJournal};
+use thumbnail_methods::{THUMBNAIL_GEN_ELF, THUMBNAIL_GEN_ID};
+
+use crate::OutputArgs;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Original image (TIFF, PNG or JPEG)
+    #[arg(long, short)]
+    input: PathBuf,
+
+    #[command(flatten)]
+    spec: SpecArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// Transformation applied to the image, committed in the journal
+#[derive(clap::Args)]
+pub struct SpecArgs {
+    /// Target width, px
+    #[arg(long, default_value_t = 75)]
+    width: u32,
+
+    /// Target height, px
+    #[arg(long, default_value_t = 75)]
+    height: u32,
+
+    /// Part of the image to keep: centered rectangle of the target aspect ratio, whole image
+    /// or an explicit rectangle (--crop-rect)
+    #[arg(long, value_enum, default_value_t = Crop::Center)]
+    crop: Crop,
+
+    /// Crop rectangle: x,y,width,height
+    #[arg(long, value_delimiter = ',', required_if_eq("crop", "rect"))]
+    crop_rect: Option<Vec<u32>>,
+
+    /// Thumbnail format
+    #[arg(long, value_enum, default_value_t = Format::Png)]
+    format: Format,
+
+    /// JPEG quality (1-100)
+    #[arg(long, default_value_t = 85)]
+    quality: u8,
+
+    /// Resampling filter, box is the fastest
+    #[arg(long, value_enum, default_value_t = Filter::Box)]
+    filter: Filter,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Crop {
+    Center,
+    None,
+    Rect,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Png,
+    Jpeg,
+    Webp,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Filter {
+    Box,
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl SpecArgs {
+    pub fn spec(&self) -> Result<ThumbnailSpec> {
+        let crop = match (self.crop, self.crop_rect.as_deref()) {
+            (Crop::Center, _) => CropMode::Center,
+            (Crop::None, _) => CropMode::None,
+            (Crop::Rect, Some(&[x, y, width, height])) => CropMode::Rect {
+                x,
+                y,
+                width,
+                height,
+            },
+            (Crop::Rect, _) => bail!("Crop rectangle must be x,y,width,height"),
+        };
+        let (format, quality) = match self.format {
+            Format::Png => (ThumbnailFormat::Png, 0),
+            Format::Jpeg => (ThumbnailFormat::Jpeg, self.quality),
+            Format::Webp => (ThumbnailFormat::WebP, 0),
+        };
+        let spec = ThumbnailSpec {
+            width: self.width,
+            height: self.height,
+            crop,
+            format,
+            quality,
+            filter: self.filter.into(),
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+}
+
+impl From<Filter> for ResizeFilter {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Box => ResizeFilter::Box,
+            Filter::Nearest => ResizeFilter::Nearest,
+            Filter::Triangle => ResizeFilter::Triangle,
+            Filter::CatmullRom => ResizeFilter::CatmullRom,
+            Filter::Gaussian => ResizeFilter::Gaussian,
+            Filter::Lanczos3 => ResizeFilter::Lanczos3,
+        }
+    }
+}
+
+pub fn run(args: Args, mode: Mode) -> Result<()> {
+    let image_bytes = std::fs::read(&args.input)
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+    let image_format = ImageFormat::sniff(&image_bytes)
+        .context("Unsupported image format, expected TIFF, PNG or JPEG")?;
+    let spec = args.spec.spec()?;
+    let mut thumbnail_bytes = Vec::new();
+
+    let env = ExecutorEnv::builder()
+        .write_slice(&spec.to_bytes())
+        .write_slice(&image_bytes)
+        .stdout(&mut thumbnail_bytes)
+        .build()?;
+
+    let prover = default_prover();
+    let prove_info = prover.prove(env, THUMBNAIL_GEN_ELF)?;
+
+    // Check that everything is OK
+    prove_info.receipt.verify(THUMBNAIL_GEN_ID).context("failed to verify")?;
+    let journal = ThumbnailJournal::from_bytes(&prove_info.receipt.journal.bytes)?;
+    ensure!(
+        journal.image_format == image_format as u8,
+        "Committed image format does not match the input"
+    );
+    ensure!(
+        journal.thumbnail_spec == spec.to_bytes(),
+        "Committed thumbnail spec does not match the input"
+    );
+
+    let artifacts = args.output.writer("thumbnail", mode)?;
+    artifacts.write_receipt(&prove_info.receipt)?;
+    artifacts.write_image_id(&THUMBNAIL_GEN_ID)?;
+    artifacts.write(
+        &format!("thumb.{}", spec.format.extension()),
+        &thumbnail_bytes,
+    )?;
+
+    println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+    println!("THUMBNAIL HASH: {}", hex::encode(journal.thumbnail_hash));
+    println!("IMAGE FORMAT: {}", image_format.mime_type());
+    println!("THUMBNAIL SPEC: {spec}");
+    println!("Artifacts: {}", artifacts.output_dir().display());
+    Ok(())
+}
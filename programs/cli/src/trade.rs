@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context, Result};
+use base64::Engine;
+use delivery_methods::DELIVERY_GEN_ID;
+use risc0_zkvm::sha::Digest;
+use risc0_zkvm::{default_prover, ExecutorEnv};
+use stock0_core::{convert_image_id, read_receipt, Mode};
+use stock0_journal::{DeliveryJournal, Journal, ThumbnailJournal, TradeJournal};
+use thumbnail_methods::THUMBNAIL_GEN_ID;
+use trade_methods::{TRADE_GEN_ELF, TRADE_GEN_ID};
+
+use crate::OutputArgs;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Receipt of the thumbnail program (listing)
+    #[arg(long)]
+    thumbnail_receipt: PathBuf,
+
+    /// Receipt of the delivery program (sale)
+    #[arg(long)]
+    delivery_receipt: PathBuf,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+pub fn run(args: Args, mode: Mode) -> Result<()> {
+    let thumbnail_receipt = read_receipt(&args.thumbnail_receipt)?;
+    thumbnail_receipt
+        .verify(THUMBNAIL_GEN_ID)
+        .context("Receipt is not a valid thumbnail proof")?;
+    let delivery_receipt = read_receipt(&args.delivery_receipt)?;
+    delivery_receipt
+        .verify(DELIVERY_GEN_ID)
+        .context("Receipt is not a valid delivery proof")?;
+
+    // Fail early instead of proving a trade that does not hold
+    let thumbnail = ThumbnailJournal::from_bytes(&thumbnail_receipt.journal.bytes)?;
+    let delivery = DeliveryJournal::from_bytes(&delivery_receipt.journal.bytes)?;
+    ensure!(
+        thumbnail.image_hash == delivery.image_hash,
+        "Thumbnail and delivery refer to different images"
+    );
+
+    let env = ExecutorEnv::builder()
+        .write(&Digest::from(THUMBNAIL_GEN_ID))?
+        .write(&Digest::from(DELIVERY_GEN_ID))?
+        .write_slice(&thumbnail_receipt.journal.bytes)
+        .write_slice(&delivery_receipt.journal.bytes)
+        .add_assumption(thumbnail_receipt)
+        .add_assumption(delivery_receipt)
+        .build()?;
+
+    let prover = default_prover();
+    let prove_info = prover.prove(env, TRADE_GEN_ELF)?;
+
+    // Check that everything is OK
+    prove_info.receipt.verify(TRADE_GEN_ID).context("failed to verify")?;
+    let journal = TradeJournal::from_bytes(&prove_info.receipt.journal.bytes)?;
+    ensure!(
+        journal.thumbnail_image_id == convert_image_id(&THUMBNAIL_GEN_ID)
+            && journal.delivery_image_id == convert_image_id(&DELIVERY_GEN_ID),
+        "Committed image IDs do not match the known programs"
+    );
+
+    let artifacts = args.output.writer("trade", mode)?;
+    artifacts.write_receipt(&prove_info.receipt)?;
+    artifacts.write_image_id(&TRADE_GEN_ID)?;
+
+    println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+    println!("THUMBNAIL HASH: {}", hex::encode(journal.thumbnail_hash));
+    println!("DATA ROOT: {}", hex::encode(journal.data_root));
+    println!("BUYER ADDRESS: 0x{}", hex::encode(journal.buyer_address));
+    println!(
+        "BLOB COMMITMENT: {}",
+        base64::engine::general_purpose::STANDARD.encode(journal.blob_commitment)
+    );
+    println!("Artifacts: {}", artifacts.output_dir().display());
+    Ok(())
+}
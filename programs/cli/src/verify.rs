@@ -0,0 +1,220 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use blobshot_methods::BLOB_ID;
+use clap::ValueEnum;
+use delivery_methods::DELIVERY_GEN_ID;
+use envelope_methods::ENVELOPE_GEN_ID;
+use pipeline_methods::PIPELINE_GEN_ID;
+use preview_methods::PREVIEW_GEN_ID;
+use stock0_core::{convert_image_id, read_receipt};
+use stock0_format::ImageFormat;
+use stock0_journal::{
+    BlobshotJournal, DeliveryJournal, EnvelopeJournal, Journal, PipelineJournal, PreviewJournal,
+    ThumbnailJournal, TradeJournal, WatermarkJournal,
+};
+use stock0_spec::{ThumbnailSpec, WatermarkSpec};
+use thumbnail_methods::THUMBNAIL_GEN_ID;
+use trade_methods::TRADE_GEN_ID;
+use watermark_methods::WATERMARK_GEN_ID;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Receipt produced by any of the programs, e.g. target/prod/delivery/receipt
+    #[arg(long)]
+    receipt: PathBuf,
+
+    /// Expected program [default: any of the known programs]
+    #[arg(long, value_enum)]
+    program: Option<Program>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Program {
+    Thumbnail,
+    Watermark,
+    Preview,
+    Pipeline,
+    Envelope,
+    Blobshot,
+    Delivery,
+    Trade,
+}
+
+impl Program {
+    const ALL: [Program; 8] = [
+        Program::Thumbnail,
+        Program::Watermark,
+        Program::Preview,
+        Program::Pipeline,
+        Program::Envelope,
+        Program::Blobshot,
+        Program::Delivery,
+        Program::Trade,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Program::Thumbnail => "thumbnail",
+            Program::Watermark => "watermark",
+            Program::Preview => "preview",
+            Program::Pipeline => "pipeline",
+            Program::Envelope => "envelope",
+            Program::Blobshot => "blobshot",
+            Program::Delivery => "delivery",
+            Program::Trade => "trade",
+        }
+    }
+
+    fn image_id(&self) -> [u32; 8] {
+        match self {
+            Program::Thumbnail => THUMBNAIL_GEN_ID,
+            Program::Watermark => WATERMARK_GEN_ID,
+            Program::Preview => PREVIEW_GEN_ID,
+            Program::Pipeline => PIPELINE_GEN_ID,
+            Program::Envelope => ENVELOPE_GEN_ID,
+            Program::Blobshot => BLOB_ID,
+            Program::Delivery => DELIVERY_GEN_ID,
+            Program::Trade => TRADE_GEN_ID,
+        }
+    }
+}
+
+pub fn run(args: Args) -> Result<()> {
+    let receipt = read_receipt(&args.receipt)?;
+
+    let program = match args.program {
+        Some(program) => {
+            receipt
+                .verify(program.image_id())
+                .with_context(|| format!("Receipt is not a valid {} proof", program.name()))?;
+            program
+        }
+        None => Program::ALL
+            .into_iter()
+            .find(|program| receipt.verify(program.image_id()).is_ok())
+            .context("Receipt does not match any known image ID")?,
+    };
+
+    println!("PROGRAM: {}", program.name());
+    println!(
+        "IMAGE ID: {}",
+        hex::encode(convert_image_id(&program.image_id()))
+    );
+
+    let journal = &receipt.journal.bytes;
+    println!("JOURNAL: 0x{}", hex::encode(journal));
+    match program {
+        Program::Thumbnail => {
+            let journal = ThumbnailJournal::from_bytes(journal)?;
+            println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+            println!("THUMBNAIL HASH: {}", hex::encode(journal.thumbnail_hash));
+            let image_format = ImageFormat::from_u8(journal.image_format)?;
+            println!("IMAGE FORMAT: {}", image_format.mime_type());
+            let spec = ThumbnailSpec::from_bytes(&journal.thumbnail_spec)?;
+            println!("THUMBNAIL SPEC: {spec}");
+        }
+        Program::Watermark => {
+            let journal = WatermarkJournal::from_bytes(journal)?;
+            println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+            println!("WATERMARK HASH: {}", hex::encode(journal.watermark_hash));
+            println!("OUTPUT HASH: {}", hex::encode(journal.output_hash));
+            let image_format = ImageFormat::from_u8(journal.image_format)?;
+            println!("IMAGE FORMAT: {}", image_format.mime_type());
+            let spec = WatermarkSpec::from_bytes(&journal.watermark_spec)?;
+            println!("WATERMARK SPEC: {spec}");
+        }
+        Program::Preview => {
+            let journal = PreviewJournal::from_bytes(journal)?;
+            println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+            println!("PARAMS HASH: {}", hex::encode(journal.params_hash));
+            println!("PREVIEW HASH: {}", hex::encode(journal.preview_hash));
+        }
+        Program::Pipeline => {
+            let journal = PipelineJournal::from_bytes(journal)?;
+            println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+            println!("PIPELINE HASH: {}", hex::encode(journal.pipeline_hash));
+            println!("OUTPUT HASH: {}", hex::encode(journal.output_hash));
+        }
+        Program::Envelope => {
+            let journal = EnvelopeJournal::from_bytes(journal)?;
+            println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+            println!(
+                "BLOB COMMITMENT: {}",
+                base64::engine::general_purpose::STANDARD.encode(journal.blob_commitment)
+            );
+            println!("BUYER PUBLIC KEY: {}", hex::encode(journal.public_key_h));
+            println!("BUYER ADDRESS: 0x{}", hex::encode(journal.buyer_address));
+            println!("DELIVERY NONCE: {}", hex::encode(journal.delivery_nonce));
+            println!(
+                "BLOB NAMESPACE: {}",
+                base64::engine::general_purpose::STANDARD.encode(journal.namespace)
+            );
+            println!("SHARE VERSION: {}", journal.share_version);
+            println!("SIGNER: 0x{}", hex::encode(journal.signer));
+        }
+        Program::Blobshot => {
+            let journal = BlobshotJournal::from_bytes(journal)?;
+            println!("DATA ROOT: {}", hex::encode(journal.data_root));
+            println!(
+                "BLOB COMMITMENT: {}",
+                base64::engine::general_purpose::STANDARD.encode(journal.blob_commitment)
+            );
+            println!(
+                "BLOB NAMESPACE: {}",
+                base64::engine::general_purpose::STANDARD.encode(journal.namespace)
+            );
+        }
+        Program::Delivery => {
+            let journal = DeliveryJournal::from_bytes(journal)?;
+            println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+            println!("DATA ROOT: {}", hex::encode(journal.data_root));
+            println!("BUYER PUBLIC KEY: {}", hex::encode(journal.public_key_h));
+            println!("BUYER ADDRESS: 0x{}", hex::encode(journal.buyer_address));
+            println!("DELIVERY NONCE: {}", hex::encode(journal.delivery_nonce));
+            println!(
+                "BLOB COMMITMENT: {}",
+                base64::engine::general_purpose::STANDARD.encode(journal.blob_commitment)
+            );
+            println!(
+                "BLOB NAMESPACE: {}",
+                base64::engine::general_purpose::STANDARD.encode(journal.namespace)
+            );
+            println!("SHARE VERSION: {}", journal.share_version);
+            println!("SIGNER: 0x{}", hex::encode(journal.signer));
+        }
+        Program::Trade => {
+            let journal = TradeJournal::from_bytes(journal)?;
+            println!(
+                "THUMBNAIL IMAGE ID: {}",
+                hex::encode(journal.thumbnail_image_id)
+            );
+            println!(
+                "DELIVERY IMAGE ID: {}",
+                hex::encode(journal.delivery_image_id)
+            );
+            println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+            println!("THUMBNAIL HASH: {}", hex::encode(journal.thumbnail_hash));
+            let image_format = ImageFormat::from_u8(journal.image_format)?;
+            println!("IMAGE FORMAT: {}", image_format.mime_type());
+            let spec = ThumbnailSpec::from_bytes(&journal.thumbnail_spec)?;
+            println!("THUMBNAIL SPEC: {spec}");
+            println!("DATA ROOT: {}", hex::encode(journal.data_root));
+            println!("BUYER PUBLIC KEY: {}", hex::encode(journal.public_key_h));
+            println!("BUYER ADDRESS: 0x{}", hex::encode(journal.buyer_address));
+            println!("DELIVERY NONCE: {}", hex::encode(journal.delivery_nonce));
+            println!(
+                "BLOB COMMITMENT: {}",
+                base64::engine::general_purpose::STANDARD.encode(journal.blob_commitment)
+            );
+            println!(
+                "BLOB NAMESPACE: {}",
+                base64::engine::general_purpose::STANDARD.encode(journal.namespace)
+            );
+            println!("SHARE VERSION: {}", journal.share_version);
+            println!("SIGNER: 0x{}", hex::encode(journal.signer));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context, Result};
+use clap::ValueEnum;
+use risc0_zkvm::{default_prover, ExecutorEnv};
+use stock0_core::Mode;
+use stock0_format::ImageFormat;
+use stock0_spec::{Anchor, WatermarkKind, WatermarkSpec};
+use stock0_journal::{Journal, WatermarkJournal};
+use watermark_methods::{WATERMARK_GEN_ELF, WATERMARK_GEN_ID};
+
+use crate::OutputArgs;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Original image (TIFF, PNG or JPEG)
+    #[arg(long, short)]
+    input: PathBuf,
+
+    #[command(flatten)]
+    asset: AssetArgs,
+
+    #[command(flatten)]
+    spec: SpecArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// Watermark to overlay, its hash is committed in the journal
+#[derive(clap::Args)]
+#[group(required = true, multiple = false)]
+struct AssetArgs {
+    /// Watermark image (TIFF, PNG or JPEG), transparency is preserved
+    #[arg(long)]
+    image: Option<PathBuf>,
+
+    /// Watermark text, rendered with the font bundled in the program
+    #[arg(long)]
+    text: Option<String>,
+}
+
+/// Placement and appearance of the watermark, committed in the journal
+#[derive(clap::Args)]
+pub struct SpecArgs {
+    /// Where to place the watermark, tile repeats it over the whole image
+    #[arg(long, value_enum, default_value_t = Position::BottomRight)]
+    anchor: Position,
+
+    /// Horizontal margin from the anchor (spacing between the tiles), px
+    #[arg(long, default_value_t = 16)]
+    offset_x: u32,
+
+    /// Vertical margin from the anchor (spacing between the tiles), px
+    #[arg(long, default_value_t = 16)]
+    offset_y: u32,
+
+    /// Font size of the text, or width the image is scaled to [default: 24 for text, original
+    /// width for images]
+    #[arg(long)]
+    size: Option<u32>,
+
+    /// Opacity, 0 (transparent) to 255 (opaque)
+    #[arg(long, default_value_t = 128)]
+    opacity: u8,
+
+    /// Text color, RGB hex
+    #[arg(long, default_value = "ffffff")]
+    color: String,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Position {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+    Tile,
+}
+
+impl SpecArgs {
+    pub fn spec(&self, kind: WatermarkKind) -> Result<WatermarkSpec> {
+        let anchor = match self.anchor {
+            Position::TopLeft => Anchor::TopLeft,
+            Position::TopRight => Anchor::TopRight,
+            Position::BottomLeft => Anchor::BottomLeft,
+            Position::BottomRight => Anchor::BottomRight,
+            Position::Center => Anchor::Center,
+            Position::Tile => Anchor::Tile,
+        };
+        let (size, color) = match kind {
+            WatermarkKind::Image => (self.size.unwrap_or(0), [0; 3]),
+            WatermarkKind::Text => {
+                let color = hex::decode(self.color.trim_start_matches('#'))
+                    .context("Color is not a valid hex string")?
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("Color must be 3 bytes long"))?;
+                (self.size.unwrap_or(24), color)
+            }
+        };
+
+        let spec = WatermarkSpec {
+            kind,
+            anchor,
+            offset_x: self.offset_x,
+            offset_y: self.offset_y,
+            size,
+            opacity: self.opacity,
+            color,
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+}
+
+pub fn run(args: Args, mode: Mode) -> Result<()> {
+    let image_bytes = std::fs::read(&args.input)
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+    let image_format = ImageFormat::sniff(&image_bytes)
+        .context("Unsupported image format, expected TIFF, PNG or JPEG")?;
+
+    let (kind, asset) = match (&args.asset.image, &args.asset.text) {
+        (Some(path), _) => {
+            let asset = std::fs::read(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            ImageFormat::sniff(&asset)
+                .context("Unsupported watermark format, expected TIFF, PNG or JPEG")?;
+            (WatermarkKind::Image, asset)
+        }
+        (None, Some(text)) => {
+            ensure!(
+                !text.is_empty() && !text.chars().any(char::is_control),
+                "Watermark text must be a non-empty single line"
+            );
+            (WatermarkKind::Text, text.as_bytes().to_vec())
+        }
+        (None, None) => unreachable!("clap requires either --image or --text"),
+    };
+    let spec = args.spec.spec(kind)?;
+    let mut output_bytes = Vec::new();
+
+    let env = ExecutorEnv::builder()
+        .write_slice(&spec.to_bytes())
+        .write(&(asset.len() as u32))?
+        .write_slice(&asset)
+        .write_slice(&image_bytes)
+        .stdout(&mut output_bytes)
+        .build()?;
+
+    let prover = default_prover();
+    let prove_info = prover.prove(env, WATERMARK_GEN_ELF)?;
+
+    // Check that everything is OK
+    prove_info.receipt.verify(WATERMARK_GEN_ID).context("failed to verify")?;
+    let journal = WatermarkJournal::from_bytes(&prove_info.receipt.journal.bytes)?;
+    ensure!(
+        journal.image_format == image_format as u8,
+        "Committed image format does not match the input"
+    );
+    ensure!(
+        journal.watermark_spec == spec.to_bytes(),
+        "Committed watermark spec does not match the input"
+    );
+
+    let artifacts = args.output.writer("watermark", mode)?;
+    artifacts.write_receipt(&prove_info.receipt)?;
+    artifacts.write_image_id(&WATERMARK_GEN_ID)?;
+    artifacts.write("preview.png", &output_bytes)?;
+
+    println!("IMAGE HASH: {}", hex::encode(journal.image_hash));
+    println!("WATERMARK HASH: {}", hex::encode(journal.watermark_hash));
+    println!("OUTPUT HASH: {}", hex::encode(journal.output_hash));
+    println!("IMAGE FORMAT: {}", image_format.mime_type());
+    println!("WATERMARK SPEC: {spec}");
+    println!("Artifacts: {}", artifacts.output_dir().display());
+    Ok(())
+}
@@ -0,0 +1,246 @@
+//! Aligned verification data (`~/.aligned/aligned_verification_data/*.json`) and the calldata
+//! of the `Market` contract calls that take it.
+//!
+//! The batch inclusion is checked the same way `AlignedLayerServiceManager.verifyBatchInclusion`
+//! does it: the leaf is keccak256 of the packed verification data commitment and the merkle
+//! path is folded in from the leaf up, the side being picked by the index in the batch.
+
+use anyhow::{ensure, Context, Result};
+use serde::Deserialize;
+use sha3::{Digest, Keccak256};
+use stock0_journal::{abi_encode, Token};
+
+const CREATE_MARKET_ITEM: &str =
+    "createMarketItem(bytes,bytes,bytes,uint256,bytes32,bytes32,bytes32,bytes20,bytes32,bytes,uint256)";
+const DELIVER_MARKET_ITEM: &str =
+    "deliverMarketItem(uint256,bytes,bytes32,bytes32,bytes32,bytes20,bytes32,bytes,uint256)";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct VerificationDataCommitment {
+    /// keccak256 of the proof (receipt)
+    pub proof_commitment: [u8; 32],
+    /// keccak256 of the public inputs (journal)
+    pub pub_input_commitment: [u8; 32],
+    /// Image ID of the program
+    pub proving_system_aux_data_commitment: [u8; 32],
+    pub proof_generator_addr: [u8; 20],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BatchInclusionProof {
+    pub merkle_path: Vec<[u8; 32]>,
+}
+
+/// Verification data stored by the Aligned CLI once the batch is verified
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct VerificationData {
+    pub verification_data_commitment: VerificationDataCommitment,
+    pub batch_merkle_root: [u8; 32],
+    pub batch_inclusion_proof: BatchInclusionProof,
+    pub index_in_batch: u64,
+}
+
+impl VerificationData {
+    /// Parses the verification data and checks its batch inclusion
+    pub fn from_json(data_str: &str) -> Result<Self> {
+        let data: Self = serde_json::from_str(data_str).context("Invalid verification data")?;
+        ensure!(
+            data.batch_root() == data.batch_merkle_root,
+            "Verification data is not included in the batch"
+        );
+        Ok(data)
+    }
+
+    /// Leaf of the batch merkle tree
+    pub fn leaf(&self) -> [u8; 32] {
+        let commitment = &self.verification_data_commitment;
+        Keccak256::new()
+            .chain_update(commitment.proof_commitment)
+            .chain_update(commitment.pub_input_commitment)
+            .chain_update(commitment.proving_system_aux_data_commitment)
+            .chain_update(commitment.proof_generator_addr)
+            .finalize()
+            .into()
+    }
+
+    /// Batch merkle root the leaf and the merkle path lead to
+    pub fn batch_root(&self) -> [u8; 32] {
+        let mut index = self.index_in_batch;
+        let mut node = self.leaf();
+        for sibling in &self.batch_inclusion_proof.merkle_path {
+            let (left, right) = if index & 1 == 0 {
+                (&node, sibling)
+            } else {
+                (sibling, &node)
+            };
+            node = Keccak256::new()
+                .chain_update(left)
+                .chain_update(right)
+                .finalize()
+                .into();
+            index /= 2;
+        }
+        node
+    }
+
+    /// Checks that the public input commitment is the one of the journal
+    pub fn ensure_journal(&self, journal: &[u8]) -> Result<()> {
+        ensure!(
+            <[u8; 32]>::from(Keccak256::digest(journal))
+                == self.verification_data_commitment.pub_input_commitment,
+            "Journal does not match the public input commitment"
+        );
+        Ok(())
+    }
+
+    /// Merkle path as a single `bytes` argument
+    pub fn merkle_proof(&self) -> Vec<u8> {
+        self.batch_inclusion_proof.merkle_path.concat()
+    }
+
+    /// Trailing arguments shared by the `Market` calls
+    fn tokens<'a>(&'a self, merkle_proof: &'a [u8]) -> [Token<'a>; 7] {
+        let commitment = &self.verification_data_commitment;
+        [
+            Token::FixedBytes(&commitment.proof_commitment),
+            Token::FixedBytes(&commitment.pub_input_commitment),
+            Token::FixedBytes(&commitment.proving_system_aux_data_commitment),
+            Token::FixedBytes(&commitment.proof_generator_addr),
+            Token::FixedBytes(&self.batch_merkle_root),
+            Token::Bytes(merkle_proof),
+            Token::Uint(self.index_in_batch as u128),
+        ]
+    }
+}
+
+/// Calldata of `Market.createMarketItem`, the verification data is the one of the thumbnail proof
+pub fn create_market_item_calldata(
+    image_hash: &[u8],
+    thumbnail_hash: &[u8],
+    manifest_cid: &[u8],
+    price: u128,
+    verification_data: &VerificationData,
+) -> Vec<u8> {
+    let merkle_proof = verification_data.merkle_proof();
+    let mut tokens = vec![
+        Token::Bytes(image_hash),
+        Token::Bytes(thumbnail_hash),
+        Token::Bytes(manifest_cid),
+        Token::Uint(price),
+    ];
+    tokens.extend(verification_data.tokens(&merkle_proof));
+    [&selector(CREATE_MARKET_ITEM)[..], &abi_encode(&tokens)].concat()
+}
+
+/// Calldata of `Market.deliverMarketItem`, the verification data is the one of the delivery proof
+pub fn deliver_market_item_calldata(
+    item_id: u128,
+    journal: &[u8],
+    verification_data: &VerificationData,
+) -> Result<Vec<u8>> {
+    verification_data.ensure_journal(journal)?;
+
+    let merkle_proof = verification_data.merkle_proof();
+    let mut tokens = vec![Token::Uint(item_id), Token::Bytes(journal)];
+    tokens.extend(verification_data.tokens(&merkle_proof));
+    Ok([&selector(DELIVER_MARKET_ITEM)[..], &abi_encode(&tokens)].concat())
+}
+
+/// First 4 bytes of keccak256 of the function signature
+fn selector(signature: &str) -> [u8; 4] {
+    Keccak256::digest(signature.as_bytes())[..4]
+        .try_into()
+        .expect("digest is 32 bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verification data of a thumbnail proof, as used by the web app tests
+    const THUMBNAIL_VERIFICATION_DATA: &str =
+        include_str!("../../../app/test/thumbnailVerificationData.json");
+
+    fn word(calldata: &[u8], index: usize) -> &[u8] {
+        &calldata[4 + 32 * index..4 + 32 * (index + 1)]
+    }
+
+    fn uint_word(calldata: &[u8], index: usize) -> u64 {
+        u64::from_be_bytes(word(calldata, index)[24..].try_into().unwrap())
+    }
+
+    #[test]
+    fn computes_selector() {
+        assert_eq!(
+            selector("transfer(address,uint256)"),
+            [0xa9, 0x05, 0x9c, 0xbb]
+        );
+    }
+
+    #[test]
+    fn parses_verification_data() {
+        let data = VerificationData::from_json(THUMBNAIL_VERIFICATION_DATA).unwrap();
+        assert_eq!(data.index_in_batch, 95);
+        assert_eq!(data.batch_inclusion_proof.merkle_path.len(), 8);
+        assert_eq!(data.merkle_proof().len(), 8 * 32);
+        assert_eq!(data.batch_root(), data.batch_merkle_root);
+    }
+
+    #[test]
+    fn rejects_excluded_verification_data() {
+        let data = VerificationData::from_json(THUMBNAIL_VERIFICATION_DATA).unwrap();
+
+        let mut value: serde_json::Value =
+            serde_json::from_str(THUMBNAIL_VERIFICATION_DATA).unwrap();
+        value["index_in_batch"] = (data.index_in_batch + 1).into();
+        assert!(VerificationData::from_json(&value.to_string()).is_err());
+
+        let mut value: serde_json::Value =
+            serde_json::from_str(THUMBNAIL_VERIFICATION_DATA).unwrap();
+        value["verification_data_commitment"]["proof_commitment"][0] = 0.into();
+        assert!(VerificationData::from_json(&value.to_string()).is_err());
+    }
+
+    #[test]
+    fn encodes_create_market_item() {
+        let data = VerificationData::from_json(THUMBNAIL_VERIFICATION_DATA).unwrap();
+        let calldata =
+            create_market_item_calldata(&[1; 34], &[2; 32], &[3; 36], 10u128.pow(18), &data);
+
+        assert_eq!(calldata[..4], selector(CREATE_MARKET_ITEM));
+        assert_eq!(word(&calldata, 3)[16..], 10u128.pow(18).to_be_bytes());
+        let commitment = &data.verification_data_commitment;
+        assert_eq!(word(&calldata, 4), commitment.proof_commitment);
+        assert_eq!(
+            word(&calldata, 6),
+            commitment.proving_system_aux_data_commitment
+        );
+        assert_eq!(word(&calldata, 7)[..20], commitment.proof_generator_addr);
+        assert_eq!(word(&calldata, 8), data.batch_merkle_root);
+        assert_eq!(uint_word(&calldata, 10), data.index_in_batch);
+
+        // Dynamic arguments follow the 11 head words
+        let offset = |index: usize| uint_word(&calldata, index);
+        assert_eq!(offset(0), 11 * 32);
+        assert_eq!(offset(1), offset(0) + 32 + 64);
+        assert_eq!(offset(2), offset(1) + 32 + 32);
+        assert_eq!(offset(9), offset(2) + 32 + 64);
+        let merkle_proof = 4 + offset(9) as usize;
+        assert_eq!(calldata[merkle_proof + 32..], data.merkle_proof());
+    }
+
+    #[test]
+    fn encodes_deliver_market_item() {
+        let journal = b"delivery journal".to_vec();
+        let mut data = VerificationData::from_json(THUMBNAIL_VERIFICATION_DATA).unwrap();
+        assert!(deliver_market_item_calldata(7, &journal, &data).is_err());
+
+        data.verification_data_commitment.pub_input_commitment = Keccak256::digest(&journal).into();
+        let calldata = deliver_market_item_calldata(7, &journal, &data).unwrap();
+        assert_eq!(calldata[..4], selector(DELIVER_MARKET_ITEM));
+        assert_eq!(uint_word(&calldata, 0), 7);
+        assert_eq!(uint_word(&calldata, 1), 9 * 32);
+        assert_eq!(uint_word(&calldata, 9), journal.len() as u64);
+        assert_eq!(&word(&calldata, 10)[..journal.len()], journal);
+    }
+}
@@ -0,0 +1,89 @@
+use std::{
+    fs::{create_dir_all, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use risc0_zkvm::Receipt;
+
+use crate::convert_image_id;
+
+/// Proving mode, determines where the artifacts are stored by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Execution only, no actual proof (`RISC0_DEV_MODE=1`)
+    Dev,
+    /// Full proof generation
+    Prod,
+}
+
+impl Mode {
+    /// Detects the mode the same way RISC0 prover does
+    pub fn from_env() -> Self {
+        if std::env::var_os("RISC0_DEV_MODE").is_some_and(|x| x == "1") {
+            Mode::Dev
+        } else {
+            Mode::Prod
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mode::Dev => "dev",
+            Mode::Prod => "prod",
+        }
+    }
+}
+
+/// Writes proving artifacts (receipt, image ID, private outputs) to a directory
+pub struct ArtifactWriter {
+    output_dir: PathBuf,
+}
+
+impl ArtifactWriter {
+    /// Creates the output directory if it does not exist
+    pub fn new(output_dir: impl Into<PathBuf>) -> Result<Self> {
+        let output_dir = output_dir.into();
+        create_dir_all(output_dir.as_path())?;
+        Ok(Self { output_dir })
+    }
+
+    /// Default layout: `programs/target/<dev / prod>/<program name>`
+    pub fn for_program(program: &str, mode: Mode) -> Result<Self> {
+        let output_dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "..", "target", mode.as_str(), program]
+            .iter()
+            .collect();
+        Self::new(output_dir)
+    }
+
+    pub fn output_dir(&self) -> &Path {
+        self.output_dir.as_path()
+    }
+
+    /// Writes arbitrary output (e.g. thumbnail or blob) and returns its path
+    pub fn write(&self, name: &str, bytes: &[u8]) -> Result<PathBuf> {
+        let path = self.output_dir.join(name);
+        let mut file = File::create(&path)?;
+        file.write_all(bytes)?;
+        Ok(path)
+    }
+
+    /// Serialized RISC0 receipt (seal + journal), compatible with Aligned
+    pub fn write_receipt(&self, receipt: &Receipt) -> Result<PathBuf> {
+        let receipt_bytes = bincode::serialize(receipt)?;
+        self.write("receipt", &receipt_bytes)
+    }
+
+    /// Serialized program ELF hash, compatible with Aligned
+    pub fn write_image_id(&self, image_id: &[u32; 8]) -> Result<PathBuf> {
+        self.write("image_id", &convert_image_id(image_id))
+    }
+}
+
+/// Loads a receipt written by [`ArtifactWriter::write_receipt`]
+pub fn read_receipt(path: &Path) -> Result<Receipt> {
+    let receipt_bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    bincode::deserialize(&receipt_bytes).context("Invalid receipt")
+}
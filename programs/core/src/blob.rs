@@ -0,0 +1,218 @@
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::{ensure, Context, Result};
+use celestia_types::nmt::NamespaceProof;
+use celestia_types::DataAvailabilityHeader;
+
+use crate::eds::load_header;
+
+/// Celestia share size in bytes
+pub const SHARE_SIZE: usize = 512;
+
+/// Rows of the original data square spanned by a blob
+///
+/// Shares are laid out row by row, so a blob starting at share `blob_index` (row-major index in
+/// the ODS) and made of `num_shares` shares covers every row between the row of its first and
+/// its last share, whether or not it starts or ends on a row boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobRowLocator {
+    /// Index of the first share of the blob in the ODS
+    pub blob_index: usize,
+    /// Number of shares in the blob (including the share headers)
+    pub num_shares: usize,
+    pub ods_width: usize,
+}
+
+impl BlobRowLocator {
+    pub fn new(blob_index: usize, num_shares: usize, ods_width: usize) -> Result<Self> {
+        ensure!(ods_width > 0, "ODS width must be positive");
+        ensure!(num_shares > 0, "Blob must have at least one share");
+        let end = blob_index
+            .checked_add(num_shares)
+            .context("Blob share range overflows")?;
+        ensure!(
+            end <= ods_width * ods_width,
+            "Blob shares {blob_index}..{end} do not fit into a {ods_width}x{ods_width} square"
+        );
+        Ok(Self {
+            blob_index,
+            num_shares,
+            ods_width,
+        })
+    }
+
+    /// Row of the first share
+    pub fn first_row(&self) -> usize {
+        self.blob_index / self.ods_width
+    }
+
+    /// Row of the last share (inclusive)
+    pub fn last_row(&self) -> usize {
+        (self.blob_index + self.num_shares - 1) / self.ods_width
+    }
+
+    pub fn rows(&self) -> Range<usize> {
+        self.first_row()..self.last_row() + 1
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.last_row() - self.first_row() + 1
+    }
+
+    /// Column range of the blob shares within the given row (one NMT range proof each)
+    pub fn row_shares(&self, row: usize) -> Range<usize> {
+        let row_start = row * self.ods_width;
+        let start = self.blob_index.max(row_start) - row_start;
+        let end = (self.blob_index + self.num_shares).min(row_start + self.ods_width) - row_start;
+        start..end
+    }
+}
+
+/// NMT range proofs from blob shares into row roots, as returned by `blob.GetProof`
+pub struct ShareProofs {
+    pub proofs: Vec<NamespaceProof>,
+    /// Start index of the first proof, i.e. the column of the first share within its row
+    /// (not the index of the blob in the ODS)
+    pub start: u64,
+}
+
+impl ShareProofs {
+    pub fn from_json(proofs_str: &str) -> Result<Self> {
+        let proofs: Vec<NamespaceProof> = serde_json::from_str(proofs_str)?;
+
+        let proofs_val: serde_json::Value = serde_json::from_str(proofs_str)?;
+        let start = proofs_val
+            .get(0)
+            .and_then(|proof| proof.get("start"))
+            .and_then(|start| start.as_u64())
+            .context("Missing start index of the first proof")?;
+
+        Ok(Self { proofs, start })
+    }
+
+    /// Rows spanned by the blob starting at share `blob_index` of the ODS (the `index` returned
+    /// by `blob.Get`), checks that there is one proof per row covering the blob shares of it
+    pub fn locate(
+        &self,
+        blob_index: usize,
+        num_shares: usize,
+        ods_width: usize,
+    ) -> Result<BlobRowLocator> {
+        let locator = BlobRowLocator::new(blob_index, num_shares, ods_width)?;
+        ensure!(
+            self.proofs.len() == locator.num_rows(),
+            "Expected one share proof per row spanned by the blob, got {} for rows {:?}",
+            self.proofs.len(),
+            locator.rows()
+        );
+        for (proof, row) in self.proofs.iter().zip(locator.rows()) {
+            let cols = locator.row_shares(row);
+            ensure!(
+                proof.start_idx() as usize == cols.start && proof.end_idx() as usize == cols.end,
+                "Share proof for row {row} does not cover shares {cols:?}"
+            );
+        }
+        Ok(locator)
+    }
+}
+
+/// Everything the blobshot and delivery hosts need to prove the inclusion of a blob, besides
+/// the blob itself: the DAH of the block, the share proofs and the position of the blob
+pub struct InclusionInputs {
+    pub dah: DataAvailabilityHeader,
+    pub share_proofs: ShareProofs,
+    /// Index of the first share of the blob in the ODS (row-major)
+    pub blob_index: usize,
+}
+
+impl InclusionInputs {
+    /// Loads the header (protobuf `ExtendedHeader`, validated) and the share proofs (JSON),
+    /// e.g. as written by [`crate::CelestiaClient::fetch_inclusion_inputs`]
+    pub fn load(header: &Path, proofs: &Path, blob_index: usize) -> Result<Self> {
+        let header_bytes = std::fs::read(header)
+            .with_context(|| format!("Failed to read {}", header.display()))?;
+        let header = load_header(&header_bytes).context("Invalid header")?;
+
+        let proofs_str = std::fs::read_to_string(proofs)
+            .with_context(|| format!("Failed to read {}", proofs.display()))?;
+        let share_proofs = ShareProofs::from_json(&proofs_str).context("Invalid proofs")?;
+
+        Ok(Self {
+            dah: header.dah,
+            share_proofs,
+            blob_index,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows and per-row columns obtained by walking the blob share by share
+    fn walk(blob_index: usize, num_shares: usize, ods_width: usize) -> Vec<(usize, Range<usize>)> {
+        let mut rows: Vec<(usize, Range<usize>)> = Vec::new();
+        for index in blob_index..blob_index + num_shares {
+            let (row, col) = (index / ods_width, index % ods_width);
+            match rows.last_mut() {
+                Some((last, cols)) if *last == row => cols.end = col + 1,
+                _ => rows.push((row, col..col + 1)),
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn matches_share_by_share_walk() {
+        for ods_width in [1, 2, 4, 8, 16] {
+            let square = ods_width * ods_width;
+            for blob_index in 0..square {
+                for num_shares in 1..=square - blob_index {
+                    let locator = BlobRowLocator::new(blob_index, num_shares, ods_width).unwrap();
+                    let expected = walk(blob_index, num_shares, ods_width);
+
+                    assert_eq!(locator.num_rows(), expected.len());
+                    assert_eq!(
+                        locator.rows(),
+                        expected[0].0..expected.last().unwrap().0 + 1
+                    );
+                    for (row, cols) in expected {
+                        assert_eq!(locator.row_shares(row), cols);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn row_boundaries() {
+        // Fills exactly one row
+        let locator = BlobRowLocator::new(8, 8, 8).unwrap();
+        assert_eq!(locator.rows(), 1..2);
+
+        // One share past the row boundary spills into the next row
+        let locator = BlobRowLocator::new(8, 9, 8).unwrap();
+        assert_eq!(locator.rows(), 1..3);
+        assert_eq!(locator.row_shares(2), 0..1);
+
+        // Unaligned start, ends on the boundary
+        let locator = BlobRowLocator::new(5, 11, 8).unwrap();
+        assert_eq!(locator.rows(), 0..2);
+        assert_eq!(locator.row_shares(0), 5..8);
+        assert_eq!(locator.row_shares(1), 0..8);
+
+        // Single share in the last cell of the square
+        let locator = BlobRowLocator::new(63, 1, 8).unwrap();
+        assert_eq!(locator.rows(), 7..8);
+        assert_eq!(locator.row_shares(7), 7..8);
+    }
+
+    #[test]
+    fn rejects_invalid_spans() {
+        assert!(BlobRowLocator::new(0, 0, 8).is_err());
+        assert!(BlobRowLocator::new(0, 1, 0).is_err());
+        assert!(BlobRowLocator::new(60, 5, 8).is_err());
+        assert!(BlobRowLocator::new(usize::MAX, 2, 8).is_err());
+    }
+}
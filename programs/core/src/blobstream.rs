@@ -0,0 +1,374 @@
+//! Blobstream attestation proofs: inclusion of a block data root into a data commitment
+//! relayed to Ethereum.
+//!
+//! Blobstream commits to ranges of Celestia blocks `[start_block, end_block)`: the data
+//! commitment is the RFC 6962 merkle root of the `abi.encode(DataRootTuple)` of every block in
+//! the range. An [`AttestationProof`] is what the contract takes to check that the data root
+//! committed by the delivery program belongs to a relayed block.
+
+use anyhow::{anyhow, ensure, Context, Result};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+/// Attestation proof file name, in the JSON shape of `app/test/blobstreamAttestationProof.json`
+pub const ATTESTATION_PROOF_FILE: &str = "attestation_proof.json";
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+/// `DataRootTuple` of the Blobstream contract
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataRootTuple {
+    pub height: u64,
+    pub data_root: [u8; 32],
+}
+
+impl DataRootTuple {
+    /// `abi.encode(tuple)`, the leaf of the data commitment tree
+    pub fn abi_encode(&self) -> [u8; 64] {
+        let mut leaf = [0u8; 64];
+        leaf[24..32].copy_from_slice(&self.height.to_be_bytes());
+        leaf[32..].copy_from_slice(&self.data_root);
+        leaf
+    }
+}
+
+/// Data commitment stored by the Blobstream contract (`DataCommitmentStored` event)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataCommitment {
+    /// Nonce the commitment is stored under (`tupleRootNonce` of the proof)
+    pub proof_nonce: u64,
+    pub start_block: u64,
+    /// Exclusive
+    pub end_block: u64,
+    pub data_commitment: [u8; 32],
+}
+
+impl DataCommitment {
+    pub fn contains(&self, height: u64) -> bool {
+        (self.start_block..self.end_block).contains(&height)
+    }
+
+    pub fn num_blocks(&self) -> u64 {
+        self.end_block.saturating_sub(self.start_block)
+    }
+}
+
+/// `BinaryMerkleProof` of the Blobstream contract, side nodes are ordered from the leaf up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryMerkleProof {
+    pub side_nodes: Vec<[u8; 32]>,
+    /// Index of the leaf
+    pub key: u64,
+    pub num_leaves: u64,
+}
+
+impl BinaryMerkleProof {
+    /// Root of the tree the leaf is included in, `None` if the proof is malformed
+    pub fn compute_root(&self, leaf: &[u8]) -> Option<[u8; 32]> {
+        if self.key >= self.num_leaves {
+            return None;
+        }
+        compute_root_from_side_nodes(self.key, self.num_leaves, leaf_hash(leaf), &self.side_nodes)
+    }
+}
+
+/// `AttestationProof` of the Blobstream contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttestationProof {
+    pub tuple_root_nonce: u64,
+    pub tuple: DataRootTuple,
+    pub proof: BinaryMerkleProof,
+}
+
+impl AttestationProof {
+    /// Proves inclusion of the data root at `height` into the data commitment.
+    /// `data_roots` are the data roots of all the blocks in the commitment range, in order.
+    pub fn build(
+        commitment: &DataCommitment,
+        data_roots: &[[u8; 32]],
+        height: u64,
+        data_root: [u8; 32],
+    ) -> Result<Self> {
+        ensure!(
+            commitment.contains(height),
+            "Block {height} is not in the data commitment range [{}, {})",
+            commitment.start_block,
+            commitment.end_block
+        );
+        ensure!(
+            data_roots.len() as u64 == commitment.num_blocks(),
+            "Expected {} data roots, got {}",
+            commitment.num_blocks(),
+            data_roots.len()
+        );
+
+        let key = height - commitment.start_block;
+        ensure!(
+            data_roots[key as usize] == data_root,
+            "Data root does not match the one of block {height}"
+        );
+
+        let leaves = tuple_leaves(commitment.start_block, data_roots);
+        ensure!(
+            merkle_root(&leaves) == commitment.data_commitment,
+            "Data roots do not match the data commitment of blocks [{}, {})",
+            commitment.start_block,
+            commitment.end_block
+        );
+
+        let mut side_nodes = Vec::new();
+        collect_side_nodes(&leaves, key as usize, &mut side_nodes);
+
+        Ok(Self {
+            tuple_root_nonce: commitment.proof_nonce,
+            tuple: DataRootTuple { height, data_root },
+            proof: BinaryMerkleProof {
+                side_nodes,
+                key,
+                num_leaves: commitment.num_blocks(),
+            },
+        })
+    }
+
+    /// Checks the proof against the data commitment (`state_dataCommitments[tupleRootNonce]`)
+    pub fn verify(&self, data_commitment: &[u8; 32]) -> bool {
+        self.proof.compute_root(&self.tuple.abi_encode()) == Some(*data_commitment)
+    }
+
+    /// JSON the marketplace contract tests consume, numbers are decimal strings
+    pub fn to_json(&self) -> Value {
+        json!({
+            "tupleRootNonce": self.tuple_root_nonce.to_string(),
+            "data_root_tuple": {
+                "data_root": hex0x(&self.tuple.data_root),
+                "height": self.tuple.height.to_string(),
+            },
+            "binary_merkle_proof": {
+                "side_nodes": self.proof.side_nodes.iter().map(|node| hex0x(node)).collect::<Vec<_>>(),
+                "key": self.proof.key.to_string(),
+                "num_leaves": self.proof.num_leaves.to_string(),
+            },
+        })
+    }
+}
+
+/// Data commitment of the blocks starting at `start_block`
+pub fn data_commitment_root(start_block: u64, data_roots: &[[u8; 32]]) -> [u8; 32] {
+    merkle_root(&tuple_leaves(start_block, data_roots))
+}
+
+/// Parses a `0x` prefixed (or bare) hex encoded 32-byte hash
+pub fn parse_hash(value: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .with_context(|| format!("Invalid hash {value}"))?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| anyhow!("Hash must be 32 bytes, got {}", bytes.len()))
+}
+
+fn hex0x(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Leaf hashes of the data root tuples of the blocks starting at `start_block`
+fn tuple_leaves(start_block: u64, data_roots: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    data_roots
+        .iter()
+        .zip(start_block..)
+        .map(|(data_root, height)| {
+            leaf_hash(
+                &DataRootTuple {
+                    height,
+                    data_root: *data_root,
+                }
+                .abi_encode(),
+            )
+        })
+        .collect()
+}
+
+fn leaf_hash(leaf: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([LEAF_PREFIX])
+        .chain_update(leaf)
+        .finalize()
+        .into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([NODE_PREFIX])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Largest power of two less than `n` (n > 1)
+fn split_point(n: usize) -> usize {
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+/// RFC 6962 root over the leaf hashes
+fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    match leaves.len() {
+        0 => Sha256::digest(b"").into(),
+        1 => leaves[0],
+        n => {
+            let k = split_point(n);
+            node_hash(&merkle_root(&leaves[..k]), &merkle_root(&leaves[k..]))
+        }
+    }
+}
+
+/// Side nodes of the leaf at `index`, from the leaf up
+fn collect_side_nodes(leaves: &[[u8; 32]], index: usize, side_nodes: &mut Vec<[u8; 32]>) {
+    if leaves.len() <= 1 {
+        return;
+    }
+    let k = split_point(leaves.len());
+    if index < k {
+        collect_side_nodes(&leaves[..k], index, side_nodes);
+        side_nodes.push(merkle_root(&leaves[k..]));
+    } else {
+        collect_side_nodes(&leaves[k..], index - k, side_nodes);
+        side_nodes.push(merkle_root(&leaves[..k]));
+    }
+}
+
+fn compute_root_from_side_nodes(
+    key: u64,
+    num_leaves: u64,
+    leaf_hash: [u8; 32],
+    side_nodes: &[[u8; 32]],
+) -> Option<[u8; 32]> {
+    if num_leaves == 1 {
+        return side_nodes.is_empty().then_some(leaf_hash);
+    }
+    let (side_node, rest) = side_nodes.split_last()?;
+    let k = split_point(num_leaves as usize) as u64;
+    if key < k {
+        let left = compute_root_from_side_nodes(key, k, leaf_hash, rest)?;
+        Some(node_hash(&left, side_node))
+    } else {
+        let right = compute_root_from_side_nodes(key - k, num_leaves - k, leaf_hash, rest)?;
+        Some(node_hash(side_node, &right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Data commitment range with the expected proof of one of its blocks
+    struct Fixture {
+        commitment: DataCommitment,
+        data_roots: Vec<[u8; 32]>,
+        height: u64,
+        attestation_proof: Value,
+    }
+
+    fn fixture() -> Fixture {
+        let fixture: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/blobstream_range.json")).unwrap();
+        Fixture {
+            commitment: DataCommitment {
+                proof_nonce: fixture["proof_nonce"].as_u64().unwrap(),
+                start_block: fixture["start_block"].as_u64().unwrap(),
+                end_block: fixture["end_block"].as_u64().unwrap(),
+                data_commitment: parse_hash(fixture["data_commitment"].as_str().unwrap()).unwrap(),
+            },
+            data_roots: fixture["data_roots"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|root| parse_hash(root.as_str().unwrap()).unwrap())
+                .collect(),
+            height: fixture["height"].as_u64().unwrap(),
+            attestation_proof: fixture["attestation_proof"].clone(),
+        }
+    }
+
+    #[test]
+    fn builds_fixture_proof() {
+        let fixture = fixture();
+        let data_root =
+            fixture.data_roots[(fixture.height - fixture.commitment.start_block) as usize];
+
+        let proof = AttestationProof::build(
+            &fixture.commitment,
+            &fixture.data_roots,
+            fixture.height,
+            data_root,
+        )
+        .unwrap();
+        assert_eq!(proof.to_json(), fixture.attestation_proof);
+        assert!(proof.verify(&fixture.commitment.data_commitment));
+        assert_eq!(
+            data_commitment_root(fixture.commitment.start_block, &fixture.data_roots),
+            fixture.commitment.data_commitment
+        );
+    }
+
+    #[test]
+    fn proves_every_block() {
+        let fixture = fixture();
+        let commitment = fixture.commitment;
+        for (height, data_root) in (commitment.start_block..).zip(fixture.data_roots.iter()) {
+            let proof =
+                AttestationProof::build(&commitment, &fixture.data_roots, height, *data_root)
+                    .unwrap();
+            assert!(proof.verify(&commitment.data_commitment));
+
+            let mut tampered = proof.clone();
+            tampered.tuple.height += 1;
+            assert!(!tampered.verify(&commitment.data_commitment));
+        }
+    }
+
+    #[test]
+    fn rejects_mismatching_inputs() {
+        let fixture = fixture();
+        let commitment = fixture.commitment;
+        let data_root = fixture.data_roots[0];
+
+        // Height out of range
+        assert!(AttestationProof::build(
+            &commitment,
+            &fixture.data_roots,
+            commitment.end_block,
+            data_root
+        )
+        .is_err());
+        // Data root of another block
+        assert!(AttestationProof::build(
+            &commitment,
+            &fixture.data_roots,
+            commitment.start_block + 1,
+            data_root
+        )
+        .is_err());
+        // Data roots of another range
+        let shifted = DataCommitment {
+            start_block: commitment.start_block + 1,
+            end_block: commitment.end_block + 1,
+            ..commitment
+        };
+        assert!(AttestationProof::build(
+            &shifted,
+            &fixture.data_roots,
+            shifted.start_block,
+            fixture.data_roots[0]
+        )
+        .is_err());
+        // Missing data roots
+        assert!(AttestationProof::build(
+            &commitment,
+            &fixture.data_roots[1..],
+            commitment.start_block,
+            data_root
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use std::ops::Range;
+
+use anyhow::{ensure, Result};
+use celestia_types::hash::Hash;
+use celestia_types::nmt::{NamespacedHash, NamespacedHashExt};
+use celestia_types::{DataAvailabilityHeader, ExtendedHeader};
+use nmt_rs::simple_merkle::db::MemDb;
+use nmt_rs::simple_merkle::proof::Proof;
+use nmt_rs::simple_merkle::tree::MerkleTree;
+use nmt_rs::TmSha2Hasher;
+
+/// Decodes a protobuf encoded extended header and validates it
+pub fn load_header(header_bytes: &[u8]) -> Result<ExtendedHeader> {
+    Ok(ExtendedHeader::decode_and_validate(header_bytes)?)
+}
+
+/// Merkle tree over the extended data square (EDS) row and column roots.
+/// Its root is the "data root" of the block.
+pub struct DataRootTree {
+    row_roots: Vec<NamespacedHash>,
+    data_root: [u8; 32],
+    tree: MerkleTree<MemDb<[u8; 32]>, TmSha2Hasher>,
+}
+
+impl DataRootTree {
+    /// Rebuilds the tree from the header and ensures it matches the header's data root
+    pub fn from_header(header: &ExtendedHeader) -> Result<Self> {
+        Self::from_dah(&header.dah)
+    }
+
+    /// Rebuilds the tree from the row and column roots and ensures it matches the DAH hash
+    pub fn from_dah(dah: &DataAvailabilityHeader) -> Result<Self> {
+        let row_roots = dah.row_roots().to_vec();
+        let column_roots = dah.column_roots();
+
+        let hasher = TmSha2Hasher {}; // Tendermint Sha2 hasher
+        let mut tree: MerkleTree<MemDb<[u8; 32]>, TmSha2Hasher> = MerkleTree::with_hasher(hasher);
+        for root in row_roots.iter().chain(column_roots.iter()) {
+            tree.push_raw_leaf(&root.to_array());
+        }
+
+        let data_root = tree.root();
+        ensure!(
+            dah.hash() == Hash::Sha256(data_root),
+            "Data root is not the merkle root of the EDS row and column roots"
+        );
+
+        Ok(Self {
+            row_roots,
+            data_root,
+            tree,
+        })
+    }
+
+    pub fn data_root(&self) -> [u8; 32] {
+        self.data_root
+    }
+
+    pub fn row_roots(&self) -> &[NamespacedHash] {
+        &self.row_roots
+    }
+
+    /// Extended data square (EDS) size
+    pub fn eds_width(&self) -> usize {
+        self.row_roots.len()
+    }
+
+    /// Original data square (ODS) size
+    pub fn ods_width(&self) -> usize {
+        self.eds_width() / 2
+    }
+
+    /// Proves inclusion of a range of row roots into the data root
+    pub fn build_row_range_proof(&mut self, rows: Range<usize>) -> Proof<TmSha2Hasher> {
+        self.tree.build_range_proof(rows)
+    }
+}
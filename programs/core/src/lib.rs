@@ -0,0 +1,33 @@
+//! Host-side building blocks shared by the Stock0 programs.
+//!
+//! Covers everything around a proving run that is not program specific:
+//! artifact layout, image ID conversion and the Celestia data square helpers
+//! used to prepare blob inclusion inputs, fetched from a Celestia node or
+//! produced offline by a mock data square, the Blobstream attestation
+//! proofs of delivered blobs and the `Market` calldata built from the Aligned
+//! verification data.
+
+pub mod aligned;
+pub mod artifacts;
+pub mod blob;
+pub mod blobstream;
+pub mod eds;
+pub mod mock;
+pub mod rpc;
+
+pub use aligned::VerificationData;
+pub use artifacts::{read_receipt, ArtifactWriter, Mode};
+pub use blob::{BlobRowLocator, InclusionInputs, ShareProofs};
+pub use blobstream::{AttestationProof, DataCommitment};
+pub use eds::DataRootTree;
+pub use mock::{MockDataSquare, MockSquareBuilder};
+pub use rpc::CelestiaClient;
+
+/// Converts RISC0 image ID into bytes (the format expected by Aligned)
+pub fn convert_image_id(data: &[u32; 8]) -> [u8; 32] {
+    let mut res = [0; 32];
+    for i in 0..8 {
+        res[4 * i..4 * (i + 1)].copy_from_slice(&data[i].to_le_bytes());
+    }
+    res
+}
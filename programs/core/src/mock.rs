@@ -0,0 +1,466 @@
+//! In-memory stand-in for Celestia data availability, to run the blob programs offline.
+//!
+//! [`MockSquareBuilder`] lays blobs out in an original data square (ODS) following the
+//! non-interactive default rules (sorted by namespace, aligned to their subtree width, gaps
+//! filled with padding shares) and extends it into the extended data square (EDS) with a
+//! systematic Reed-Solomon code over GF(2^8). The resulting [`MockDataSquare`] gives the data
+//! availability header (DAH), the NMT share proofs (as returned by `blob.GetProof`) and the row
+//! range proofs that blobshot and delivery take as inputs. [`MockDataSquare::inclusion_inputs`]
+//! packs them the way the hosts load them from a node, so that the programs can be executed on
+//! arbitrary blobs (see the blobshot and delivery tests of the CLI).
+//!
+//! There is no mock `ExtendedHeader`: it would need a commit signed by a validator set, and the
+//! hosts only take the DAH out of it.
+//!
+//! Parity shares are not Leopard encoded, so the roots differ from the ones a node would compute
+//! for the same blobs. They are consistent with each other, which is all the programs check.
+
+use anyhow::{anyhow, ensure, Context, Result};
+use base64::Engine;
+use celestia_types::nmt::{
+    Namespace, NamespaceProof, NamespacedHash, NamespacedHashExt, NamespacedSha2Hasher, Nmt,
+    NS_SIZE,
+};
+use celestia_types::DataAvailabilityHeader;
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use stock0_celestia::{min_square_size, subtree_width, SUBTREE_ROOT_THRESHOLD};
+
+use crate::blob::{BlobRowLocator, InclusionInputs, ShareProofs, SHARE_SIZE};
+use crate::eds::DataRootTree;
+
+/// Max width of the ODS (the EDS row must fit into GF(2^8))
+pub const MAX_ODS_WIDTH: usize = 128;
+
+type Share = [u8; SHARE_SIZE];
+
+/// Position of a blob in the ODS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobPlacement {
+    pub namespace: Namespace,
+    /// Index of the first share (row-major)
+    pub index: usize,
+    pub num_shares: usize,
+}
+
+/// Collects the blobs of a block
+#[derive(Default)]
+pub struct MockSquareBuilder {
+    blobs: Vec<(Namespace, Vec<Share>)>,
+}
+
+impl MockSquareBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the shares of a blob, returns its ID in [`MockDataSquare::placement`]
+    pub fn add_blob(&mut self, namespace: Namespace, shares: Vec<Share>) -> Result<usize> {
+        ensure!(!shares.is_empty(), "Blob must have at least one share");
+        ensure!(
+            shares
+                .iter()
+                .all(|share| &share[..NS_SIZE] == namespace.as_bytes()),
+            "Blob shares must be in the blob namespace"
+        );
+        self.blobs.push((namespace, shares));
+        Ok(self.blobs.len() - 1)
+    }
+
+    /// Lays the blobs out and extends the square
+    pub fn build(self) -> Result<MockDataSquare> {
+        // Blobs are ordered by namespace, so that the rows are valid NMTs
+        let mut order: Vec<usize> = (0..self.blobs.len()).collect();
+        order.sort_by(|a, b| self.blobs[*a].0.as_bytes().cmp(self.blobs[*b].0.as_bytes()));
+
+        let mut ods: Vec<Share> = Vec::new();
+        let mut placements = vec![None; self.blobs.len()];
+        for id in order {
+            let (namespace, shares) = &self.blobs[id];
+
+            // Each blob starts at a multiple of its subtree width
+            let index = ods
+                .len()
+                .next_multiple_of(subtree_width(shares.len(), SUBTREE_ROOT_THRESHOLD));
+            if let Some(last) = ods.last() {
+                let padding = padding_share(&last[..NS_SIZE]);
+                ods.resize(index, padding);
+            }
+            ods.extend_from_slice(shares);
+
+            placements[id] = Some(BlobPlacement {
+                namespace: *namespace,
+                index,
+                num_shares: shares.len(),
+            });
+        }
+
+        let ods_width = min_square_size(ods.len());
+        ensure!(
+            ods_width <= MAX_ODS_WIDTH,
+            "Blobs take {} shares, more than a {MAX_ODS_WIDTH}x{MAX_ODS_WIDTH} square",
+            ods.len()
+        );
+        ods.resize(
+            ods_width * ods_width,
+            padding_share(Namespace::TAIL_PADDING.as_bytes()),
+        );
+
+        let eds = extend(&ods, ods_width)?;
+        let row_roots = (0..2 * ods_width)
+            .map(|row| Ok(axis_tree(&eds, ods_width, Axis::Row, row)?.root()))
+            .collect::<Result<Vec<_>>>()?;
+        let column_roots = (0..2 * ods_width)
+            .map(|col| Ok(axis_tree(&eds, ods_width, Axis::Column, col)?.root()))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(MockDataSquare {
+            ods_width,
+            eds,
+            dah: build_dah(&row_roots, &column_roots)?,
+            placements: placements.into_iter().flatten().collect(),
+        })
+    }
+}
+
+/// Extended data square with the blobs laid out
+pub struct MockDataSquare {
+    ods_width: usize,
+    /// Row-major, `2 * ods_width` shares per row
+    eds: Vec<Share>,
+    dah: DataAvailabilityHeader,
+    placements: Vec<BlobPlacement>,
+}
+
+impl MockDataSquare {
+    pub fn ods_width(&self) -> usize {
+        self.ods_width
+    }
+
+    pub fn dah(&self) -> &DataAvailabilityHeader {
+        &self.dah
+    }
+
+    /// Merkle tree over the DAH, for the data root and the row range proofs
+    pub fn data_root_tree(&self) -> Result<DataRootTree> {
+        DataRootTree::from_dah(&self.dah)
+    }
+
+    /// Position of the blob with the given ID (as returned by [`MockSquareBuilder::add_blob`])
+    pub fn placement(&self, blob_id: usize) -> Option<BlobPlacement> {
+        self.placements.get(blob_id).copied()
+    }
+
+    /// Share of the EDS
+    pub fn share(&self, row: usize, col: usize) -> &Share {
+        &self.eds[row * 2 * self.ods_width + col]
+    }
+
+    /// Shares of the blob, in order
+    pub fn blob_shares(&self, blob_id: usize) -> Result<Vec<Share>> {
+        let placement = self.placement(blob_id).context("Unknown blob")?;
+        Ok((placement.index..placement.index + placement.num_shares)
+            .map(|index| *self.share(index / self.ods_width, index % self.ods_width))
+            .collect())
+    }
+
+    /// NMT range proofs of the blob shares, one per row spanned by the blob
+    pub fn share_proofs(&self, blob_id: usize) -> Result<ShareProofs> {
+        let placement = self.placement(blob_id).context("Unknown blob")?;
+        let locator = BlobRowLocator::new(placement.index, placement.num_shares, self.ods_width)?;
+
+        let proofs = locator
+            .rows()
+            .map(|row| {
+                let mut tree = axis_tree(&self.eds, self.ods_width, Axis::Row, row)?;
+                let proof = nmt_rs::NamespaceProof::PresenceProof {
+                    proof: tree.build_range_proof(locator.row_shares(row)),
+                    ignore_max_ns: true,
+                };
+                Ok(NamespaceProof::from(proof))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(ShareProofs {
+            proofs,
+            start: locator.row_shares(locator.first_row()).start as u64,
+        })
+    }
+
+    /// Inputs of the blobshot and delivery hosts for the blob, in place of the header and the
+    /// share proofs fetched from a node
+    pub fn inclusion_inputs(&self, blob_id: usize) -> Result<InclusionInputs> {
+        let placement = self.placement(blob_id).context("Unknown blob")?;
+        Ok(InclusionInputs {
+            dah: self.dah.clone(),
+            share_proofs: self.share_proofs(blob_id)?,
+            blob_index: placement.index,
+        })
+    }
+}
+
+/// Padding share of the namespace: sequence start with zero length
+fn padding_share(namespace: &[u8]) -> Share {
+    let mut share = [0u8; SHARE_SIZE];
+    share[..NS_SIZE].copy_from_slice(namespace);
+    share[NS_SIZE] = 1; // share version 0, sequence start
+    share
+}
+
+/// Extends the rows, then all of the columns (the code is linear, so Q3 is the same
+/// whether it is derived from Q1 or Q2)
+fn extend(ods: &[Share], ods_width: usize) -> Result<Vec<Share>> {
+    let eds_width = 2 * ods_width;
+    let codec = ReedSolomon::new(ods_width, ods_width)
+        .map_err(|err| anyhow!("Failed to create the codec: {err:?}"))?;
+
+    let mut eds = vec![[0u8; SHARE_SIZE]; eds_width * eds_width];
+    for row in 0..ods_width {
+        let mut shards = ods[row * ods_width..(row + 1) * ods_width].to_vec();
+        shards.resize(eds_width, [0u8; SHARE_SIZE]);
+        codec
+            .encode(&mut shards)
+            .map_err(|err| anyhow!("Failed to extend row {row}: {err:?}"))?;
+        eds[row * eds_width..(row + 1) * eds_width].copy_from_slice(&shards);
+    }
+    for col in 0..eds_width {
+        let mut shards: Vec<Share> = (0..eds_width)
+            .map(|row| eds[row * eds_width + col])
+            .collect();
+        codec
+            .encode(&mut shards)
+            .map_err(|err| anyhow!("Failed to extend column {col}: {err:?}"))?;
+        for (row, share) in shards.into_iter().enumerate().skip(ods_width) {
+            eds[row * eds_width + col] = share;
+        }
+    }
+    Ok(eds)
+}
+
+#[derive(Clone, Copy)]
+enum Axis {
+    Row,
+    Column,
+}
+
+/// NMT of an EDS row or column, parity shares are in the parity namespace
+fn axis_tree(eds: &[Share], ods_width: usize, axis: Axis, index: usize) -> Result<Nmt> {
+    let eds_width = 2 * ods_width;
+    let mut tree = Nmt::with_hasher(NamespacedSha2Hasher::with_ignore_max_ns(true));
+    for i in 0..eds_width {
+        let (row, col) = match axis {
+            Axis::Row => (index, i),
+            Axis::Column => (i, index),
+        };
+        let share = &eds[row * eds_width + col];
+        let namespace = if row < ods_width && col < ods_width {
+            Namespace::from_raw(&share[..NS_SIZE])?
+        } else {
+            Namespace::PARITY_SHARE
+        };
+        tree.push_leaf(share, namespace.into())
+            .map_err(|err| anyhow!("Failed to build the NMT: {err:?}"))?;
+    }
+    Ok(tree)
+}
+
+/// DAH in the form returned by the node
+fn build_dah(
+    row_roots: &[NamespacedHash],
+    column_roots: &[NamespacedHash],
+) -> Result<DataAvailabilityHeader> {
+    let encode = |roots: &[NamespacedHash]| -> Vec<String> {
+        roots
+            .iter()
+            .map(|root| base64::engine::general_purpose::STANDARD.encode(root.to_array()))
+            .collect()
+    };
+    let dah = serde_json::json!({
+        "row_roots": encode(row_roots),
+        "column_roots": encode(column_roots),
+    });
+    serde_json::from_value(dah).context("Invalid data availability header")
+}
+
+#[cfg(test)]
+mod tests {
+    use celestia_types::nmt::MerkleHash;
+    use nmt_rs::TmSha2Hasher;
+    use stock0_celestia::ShareVersion;
+
+    use super::*;
+
+    fn blob(id: u8, len: usize, version: ShareVersion) -> (Namespace, Vec<Share>) {
+        let namespace = stock0_celestia::namespace_v0(&[0xff, id]).unwrap();
+        let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        let shares = stock0_celestia::split_blob_to_shares(namespace, version, &data).unwrap();
+        (namespace, shares)
+    }
+
+    fn build(blobs: &[(Namespace, Vec<Share>)]) -> MockDataSquare {
+        let mut builder = MockSquareBuilder::new();
+        for (namespace, shares) in blobs {
+            builder.add_blob(*namespace, shares.clone()).unwrap();
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn proves_every_blob() {
+        let signer = ShareVersion::V1 { signer: [7u8; 20] };
+        let blobs = vec![
+            blob(3, 40_000, ShareVersion::V0),
+            blob(1, 100, ShareVersion::V0),
+            blob(2, 5_000, signer),
+            blob(2, 600, ShareVersion::V0),
+        ];
+        let square = build(&blobs);
+        let mut data_root_tree = square.data_root_tree().unwrap();
+        let row_roots = data_root_tree.row_roots().to_vec();
+
+        for (id, (namespace, shares)) in blobs.iter().enumerate() {
+            assert_eq!(&square.blob_shares(id).unwrap(), shares);
+
+            // Same checks as the blobshot and delivery guests
+            let placement = square.placement(id).unwrap();
+            let locator =
+                BlobRowLocator::new(placement.index, placement.num_shares, square.ods_width())
+                    .unwrap();
+            let share_proofs = square.share_proofs(id).unwrap();
+            assert_eq!(share_proofs.proofs.len(), locator.num_rows());
+            assert_eq!(
+                share_proofs.start as usize,
+                placement.index % square.ods_width()
+            );
+
+            let mut start = 0;
+            for (proof, row) in share_proofs.proofs.iter().zip(locator.rows()) {
+                let end = start + (proof.end_idx() - proof.start_idx()) as usize;
+                proof
+                    .verify_range(&row_roots[row], &shares[start..end], (*namespace).into())
+                    .unwrap();
+                start = end;
+            }
+            assert_eq!(start, shares.len());
+
+            let hasher = TmSha2Hasher {};
+            let leaves: Vec<[u8; 32]> = row_roots[locator.rows()]
+                .iter()
+                .map(|root| hasher.hash_leaf(&root.to_array()))
+                .collect();
+            data_root_tree
+                .build_row_range_proof(locator.rows())
+                .verify_range(&data_root_tree.data_root(), &leaves)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn locates_blob_past_first_row() {
+        let blobs = vec![
+            blob(1, 30_000, ShareVersion::V0),
+            // Starts in the middle of a row and spans several rows
+            blob(2, 20_000, ShareVersion::V0),
+        ];
+        let square = build(&blobs);
+        let placement = square.placement(1).unwrap();
+        let width = square.ods_width();
+        assert!(placement.index >= width && placement.index % width != 0);
+
+        let share_proofs = square.share_proofs(1).unwrap();
+        let locator = share_proofs
+            .locate(placement.index, placement.num_shares, width)
+            .unwrap();
+        assert_eq!(locator.first_row(), placement.index / width);
+        assert!(locator.num_rows() > 1);
+
+        let row_roots = square.data_root_tree().unwrap().row_roots().to_vec();
+        let shares = &blobs[1].1;
+        let mut start = 0;
+        for (proof, row) in share_proofs.proofs.iter().zip(locator.rows()) {
+            let end = start + (proof.end_idx() - proof.start_idx()) as usize;
+            proof
+                .verify_range(&row_roots[row], &shares[start..end], blobs[1].0.into())
+                .unwrap();
+            start = end;
+        }
+        assert_eq!(start, shares.len());
+
+        // Start of the first proof is a column, taken for the blob index it points at the rows
+        // of the other blob
+        let misplaced =
+            BlobRowLocator::new(share_proofs.start as usize, placement.num_shares, width).unwrap();
+        assert_ne!(misplaced.rows(), locator.rows());
+        let proof = &share_proofs.proofs[0];
+        let first_row_shares = &shares[..(proof.end_idx() - proof.start_idx()) as usize];
+        assert!(proof
+            .verify_range(
+                &row_roots[misplaced.first_row()],
+                first_row_shares,
+                blobs[1].0.into()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn lays_out_by_namespace_and_alignment() {
+        let blobs = vec![
+            // 70 shares, aligned to a subtree width of 2
+            blob(2, 70 * 478, ShareVersion::V0),
+            blob(1, 100, ShareVersion::V0),
+        ];
+        let square = build(&blobs);
+
+        let first = square.placement(1).unwrap();
+        let second = square.placement(0).unwrap();
+        assert_eq!((first.index, first.num_shares), (0, 1));
+        assert_eq!((second.index, second.num_shares), (2, 70));
+
+        // Namespace padding in between, tail padding at the end
+        let width = square.ods_width();
+        assert_eq!(width, 16);
+        let gap = square.share(0, 1);
+        assert_eq!(&gap[..NS_SIZE], first.namespace.as_bytes());
+        let tail = square.share(width - 1, width - 1);
+        assert_eq!(&tail[..NS_SIZE], Namespace::TAIL_PADDING.as_bytes());
+    }
+
+    #[test]
+    fn extends_with_erasure_code() {
+        let square = build(&[blob(1, 20_000, ShareVersion::V0)]);
+        let width = square.ods_width();
+        let codec = ReedSolomon::new(width, width).unwrap();
+
+        // Any row and any column can be recovered from its parity half
+        for index in [0, width - 1, width, 2 * width - 1] {
+            for axis in [Axis::Row, Axis::Column] {
+                let cell = |i: usize| match axis {
+                    Axis::Row => square.share(index, i),
+                    Axis::Column => square.share(i, index),
+                };
+                let mut shards: Vec<Option<Vec<u8>>> = (0..2 * width)
+                    .map(|i| (i >= width).then(|| cell(i).to_vec()))
+                    .collect();
+                codec.reconstruct(&mut shards).unwrap();
+                for (i, shard) in shards.iter().enumerate().take(width) {
+                    assert_eq!(shard.as_deref().unwrap(), cell(i));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_blocks() {
+        let mut builder = MockSquareBuilder::new();
+        let (namespace, shares) = blob(1, 10, ShareVersion::V0);
+        builder
+            .add_blob(
+                namespace,
+                vec![shares[0]; MAX_ODS_WIDTH * MAX_ODS_WIDTH + 1],
+            )
+            .unwrap();
+        assert!(builder.build().is_err());
+
+        let other = stock0_celestia::namespace_v0(&[0xff, 2]).unwrap();
+        assert!(MockSquareBuilder::new().add_blob(other, shares).is_err());
+    }
+}
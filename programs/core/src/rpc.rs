@@ -0,0 +1,400 @@
+//! Client of the celestia-node JSON-RPC API: blob submission and retrieval of the
+//! blob inclusion inputs (`header.dat`, `proofs.json` and the blob index) consumed by the hosts.
+
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context, Result};
+use base64::Engine;
+use celestia_tendermint_proto::Protobuf;
+use celestia_types::hash::Hash;
+use celestia_types::nmt::Namespace;
+use celestia_types::ExtendedHeader;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use stock0_celestia::ShareVersion;
+
+/// Default address of a local light node
+pub const DEFAULT_RPC_URL: &str = "http://127.0.0.1:26658";
+
+/// Header file name, protobuf encoded `ExtendedHeader` (see [`crate::eds::load_header`])
+pub const HEADER_FILE: &str = "header.dat";
+
+/// Share proofs file name, as returned by `blob.GetProof` (see [`crate::ShareProofs`])
+pub const PROOFS_FILE: &str = "proofs.json";
+
+/// Blob index file name, index of the first share of the blob in the ODS (decimal),
+/// passed to the hosts as `--blob-index`
+pub const BLOB_INDEX_FILE: &str = "blob_index";
+
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+pub struct CelestiaClient {
+    url: String,
+    auth_token: Option<String>,
+    agent: ureq::Agent,
+    next_id: AtomicU64,
+}
+
+/// Blob published by [`CelestiaClient::submit_blob`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubmittedBlob {
+    /// Height of the block the blob was included in
+    pub height: u64,
+    pub commitment: [u8; 32],
+}
+
+impl CelestiaClient {
+    /// Auth token is required by the node for `blob.Submit` (write access)
+    pub fn new(url: &str, auth_token: Option<String>) -> Self {
+        Self {
+            url: url.to_string(),
+            auth_token,
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// `blob.Submit`, waits until the blob is included into a block
+    pub fn submit_blob(
+        &self,
+        namespace: Namespace,
+        share_version: ShareVersion,
+        data: &[u8],
+        gas_price: f64,
+    ) -> Result<SubmittedBlob> {
+        let shares = stock0_celestia::split_blob_to_shares(namespace, share_version, data)?;
+        let commitment = stock0_celestia::blob_commitment(namespace, &shares)?;
+
+        let mut blob = json!({
+            "namespace": base64(namespace.as_bytes()),
+            "data": base64(data),
+            "share_version": share_version.as_u8(),
+            "commitment": base64(&commitment),
+        });
+        // Share version 1 commits to the signer, the node has to sign with the same account
+        if let ShareVersion::V1 { signer } = share_version {
+            blob["signer"] = json!(base64(&signer));
+        }
+        let height = self.call("blob.Submit", json!([[blob], gas_price]))?;
+        Ok(SubmittedBlob { height, commitment })
+    }
+
+    /// `blob.GetProof`, NMT proofs of the blob shares (one per row)
+    pub fn get_proof(&self, height: u64, namespace: Namespace, commitment: &[u8]) -> Result<Value> {
+        self.call(
+            "blob.GetProof",
+            json!([height, base64(namespace.as_bytes()), base64(commitment)]),
+        )
+    }
+
+    /// `blob.Get`, the blob along with the index of its first share in the ODS
+    pub fn get_blob(&self, height: u64, namespace: Namespace, commitment: &[u8]) -> Result<Value> {
+        self.call(
+            "blob.Get",
+            json!([height, base64(namespace.as_bytes()), base64(commitment)]),
+        )
+    }
+
+    /// `header.GetByHeight`
+    pub fn get_header(&self, height: u64) -> Result<ExtendedHeader> {
+        self.call("header.GetByHeight", json!([height]))
+    }
+
+    /// Data roots of a range of blocks, e.g. the one of a Blobstream data commitment
+    pub fn get_data_roots(&self, blocks: Range<u64>) -> Result<Vec<[u8; 32]>> {
+        blocks
+            .map(|height| {
+                let header = self.get_header(height)?;
+                header
+                    .validate()
+                    .with_context(|| format!("Invalid header {height}"))?;
+                ensure!(
+                    header.height().value() == height,
+                    "Node returned header {} instead of {height}",
+                    header.height()
+                );
+                match header.dah.hash() {
+                    Hash::Sha256(data_root) => Ok(data_root),
+                    Hash::None => bail!("Header {height} has no data root"),
+                }
+            })
+            .collect()
+    }
+
+    /// Fetches the header, the share proofs and the index of the blob and writes them into `dir`
+    /// in the format the blobshot and delivery hosts consume. Returns the blob index.
+    pub fn fetch_inclusion_inputs(
+        &self,
+        height: u64,
+        namespace: Namespace,
+        commitment: &[u8],
+        dir: &Path,
+    ) -> Result<u64> {
+        let header = self.get_header(height)?;
+        header.validate().context("Invalid header")?;
+        let proofs = self.get_proof(height, namespace, commitment)?;
+
+        let blob = self.get_blob(height, namespace, commitment)?;
+        ensure!(
+            blob["commitment"] == base64(commitment),
+            "Node returned a blob with another commitment"
+        );
+        let blob_index = blob["index"]
+            .as_u64()
+            .context("Node did not return the blob index")?;
+
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        std::fs::write(dir.join(HEADER_FILE), header.encode_vec()?)
+            .context("Failed to write the header")?;
+        std::fs::write(dir.join(PROOFS_FILE), serde_json::to_string(&proofs)?)
+            .context("Failed to write the proofs")?;
+        std::fs::write(dir.join(BLOB_INDEX_FILE), blob_index.to_string())
+            .context("Failed to write the blob index")?;
+        Ok(blob_index)
+    }
+
+    fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut request = self.agent.post(&self.url);
+        if let Some(auth_token) = &self.auth_token {
+            request = request.set("Authorization", &format!("Bearer {auth_token}"));
+        }
+        let response: Value = request
+            .send_json(json!({
+                "id": id,
+                "jsonrpc": "2.0",
+                "method": method,
+                "params": params,
+            }))
+            .with_context(|| format!("{method} request failed"))?
+            .into_json()
+            .with_context(|| format!("Invalid {method} response"))?;
+
+        if let Some(error) = response.get("error") {
+            bail!("{method} failed: {error}");
+        }
+        let result = response
+            .get("result")
+            .with_context(|| format!("Missing {method} result"))?;
+        serde_json::from_value(result.clone()).with_context(|| format!("Invalid {method} result"))
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    use super::*;
+
+    /// Serves one canned result per request, returns the received requests
+    fn stub_server(results: Vec<Value>) -> (String, JoinHandle<Vec<(Option<String>, Value)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for result in results {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut content_length = 0;
+                let mut auth = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(": ") {
+                        match name.to_ascii_lowercase().as_str() {
+                            "content-length" => content_length = value.parse().unwrap(),
+                            "authorization" => auth = Some(value.to_string()),
+                            _ => {}
+                        }
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let request: Value = serde_json::from_slice(&body).unwrap();
+
+                let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+                let response = serde_json::to_vec(&response).unwrap();
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    response.len()
+                )
+                .unwrap();
+                stream.write_all(&response).unwrap();
+
+                requests.push((auth, request));
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    fn namespace() -> Namespace {
+        stock0_celestia::namespace_v0(&[1, 2, 3, 4, 5]).unwrap()
+    }
+
+    #[test]
+    fn submits_blob() {
+        let (url, server) = stub_server(vec![json!(1234)]);
+        let client = CelestiaClient::new(&url, Some("token".to_string()));
+
+        let data = b"encrypted image".to_vec();
+        let blob = client
+            .submit_blob(namespace(), ShareVersion::V0, &data, 0.1)
+            .unwrap();
+        assert_eq!(blob.height, 1234);
+
+        let requests = server.join().unwrap();
+        let (auth, request) = &requests[0];
+        assert_eq!(auth.as_deref(), Some("Bearer token"));
+        assert_eq!(request["method"], "blob.Submit");
+
+        let params = &request["params"][0][0];
+        let shares =
+            stock0_celestia::split_blob_to_shares(namespace(), ShareVersion::V0, &data).unwrap();
+        let commitment = stock0_celestia::blob_commitment(namespace(), &shares).unwrap();
+        assert_eq!(blob.commitment, commitment);
+        assert_eq!(params["commitment"], base64(&commitment));
+        assert_eq!(params["namespace"], base64(namespace().as_bytes()));
+        assert_eq!(params["data"], base64(&data));
+        assert_eq!(params["share_version"], 0);
+        assert!(params.get("signer").is_none());
+        assert_eq!(request["params"][1], 0.1);
+    }
+
+    #[test]
+    fn submits_blob_with_signer() {
+        let (url, server) = stub_server(vec![json!(1234)]);
+        let client = CelestiaClient::new(&url, Some("token".to_string()));
+
+        let share_version = ShareVersion::V1 { signer: [9u8; 20] };
+        let data = b"encrypted image".to_vec();
+        let blob = client
+            .submit_blob(namespace(), share_version, &data, 0.1)
+            .unwrap();
+
+        let requests = server.join().unwrap();
+        let params = &requests[0].1["params"][0][0];
+        let shares =
+            stock0_celestia::split_blob_to_shares(namespace(), share_version, &data).unwrap();
+        let commitment = stock0_celestia::blob_commitment(namespace(), &shares).unwrap();
+        assert_eq!(blob.commitment, commitment);
+        assert_eq!(params["share_version"], 1);
+        assert_eq!(params["signer"], base64(&[9u8; 20]));
+    }
+
+    #[test]
+    fn writes_inclusion_inputs() {
+        // Serve the header and proofs of the blobshot fixture back
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../blobshot/tests");
+        let header_bytes = std::fs::read(fixtures.join(HEADER_FILE)).unwrap();
+        let header = ExtendedHeader::decode_and_validate(&header_bytes).unwrap();
+        let proofs: Value =
+            serde_json::from_str(&std::fs::read_to_string(fixtures.join(PROOFS_FILE)).unwrap())
+                .unwrap();
+        let height = header.height().value();
+
+        let commitment = [7u8; 32];
+        let blob = json!({
+            "namespace": base64(namespace().as_bytes()),
+            "data": base64(b"encrypted image"),
+            "share_version": 0,
+            "commitment": base64(&commitment),
+            "index": 8,
+        });
+
+        let (url, server) = stub_server(vec![serde_json::to_value(&header).unwrap(), proofs, blob]);
+        let client = CelestiaClient::new(&url, None);
+
+        let dir = std::env::temp_dir().join(format!("stock0-rpc-{}", std::process::id()));
+        let blob_index = client
+            .fetch_inclusion_inputs(height, namespace(), &commitment, &dir)
+            .unwrap();
+        assert_eq!(blob_index, 8);
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[0].1["method"], "header.GetByHeight");
+        assert_eq!(requests[0].1["params"], json!([height]));
+        assert_eq!(requests[1].1["method"], "blob.GetProof");
+        assert_eq!(
+            requests[1].1["params"],
+            json!([height, base64(namespace().as_bytes()), base64(&commitment)])
+        );
+        assert_eq!(requests[2].1["method"], "blob.Get");
+        assert_eq!(requests[2].1["params"], requests[1].1["params"]);
+
+        // The hosts can load what was written
+        let written = std::fs::read(dir.join(HEADER_FILE)).unwrap();
+        assert_eq!(crate::eds::load_header(&written).unwrap(), header);
+        let proofs_str = std::fs::read_to_string(dir.join(PROOFS_FILE)).unwrap();
+        let share_proofs = crate::ShareProofs::from_json(&proofs_str).unwrap();
+        assert!(!share_proofs.proofs.is_empty());
+        let blob_index = std::fs::read_to_string(dir.join(BLOB_INDEX_FILE)).unwrap();
+        assert_eq!(blob_index, "8");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn fetches_data_roots() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../blobshot/tests");
+        let header_bytes = std::fs::read(fixtures.join(HEADER_FILE)).unwrap();
+        let header = ExtendedHeader::decode_and_validate(&header_bytes).unwrap();
+        let height = header.height().value();
+
+        let header_json = serde_json::to_value(&header).unwrap();
+        let (url, server) = stub_server(vec![header_json.clone(), header_json]);
+        let client = CelestiaClient::new(&url, None);
+
+        let data_roots = client.get_data_roots(height..height + 1).unwrap();
+        assert_eq!(data_roots.len(), 1);
+        assert_eq!(Hash::Sha256(data_roots[0]), header.dah.hash());
+
+        // The node must return the requested block
+        let err = client.get_data_roots(height + 1..height + 2).unwrap_err();
+        assert!(err.to_string().contains("instead of"));
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests[1].1["params"], json!([height + 1]));
+    }
+
+    #[test]
+    fn reports_rpc_errors() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).unwrap();
+            let body = r#"{"jsonrpc":"2.0","id":1,"error":{"code":1,"message":"blob: not found"}}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+        });
+
+        let client = CelestiaClient::new(&url, None);
+        let err = client.get_proof(1, namespace(), &[0u8; 32]).unwrap_err();
+        assert!(err.to_string().contains("blob: not found"));
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,521 @@
+//! Envelope encryption shared by the host and the guest programs.
+//!
+//! The image is encrypted with ChaCha20 under a key derived from a random
+//! session point `S = s * G`, which is in turn encrypted for the buyer with
+//! ElGamal: `c1 = r * G`, `c2 = S + r * H`, where `H` is the buyer's public key.
+//!
+//! ChaCha key and nonce are derived with HKDF-SHA256 from `S` salted with a
+//! random per-delivery nonce, so that selling the same image twice never
+//! reuses a keystream. The delivery nonce is carried in the blob and committed
+//! in the journal.
+//!
+//! Optionally the image can be encrypted with ChaCha20-Poly1305, the tag is
+//! appended to the ciphertext and the associated data binds the image hash and
+//! the buyer's public key, so that a tampered blob is rejected by the buyer.
+//!
+//! The blob is serialized in the envelope format defined in `stock0-format`.
+//! [`Sealer`] produces the same blob incrementally, so that large images can be
+//! encrypted chunk by chunk.
+//!
+//! The buyer's Ethereum address is derived from `H` and committed alongside it,
+//! so that a contract can match the delivery against the purchasing account.
+
+use std::fmt;
+use std::ops::Mul;
+
+use chacha20::cipher::{NewCipher, StreamCipher, StreamCipherSeek};
+use chacha20::ChaCha20;
+use chacha20poly1305::aead::{AeadInPlace, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Tag};
+use hkdf::Hkdf;
+use k256::elliptic_curve::{group::GroupEncoding, sec1::ToEncodedPoint, PrimeField, PublicKey};
+use k256::{AffinePoint, ProjectivePoint, Scalar, Secp256k1};
+use poly1305::universal_hash::{NewUniversalHash, UniversalHash};
+use poly1305::Poly1305;
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+pub use stock0_format::{
+    sniff_mime_type, CipherSuite, Header, KeyWrap, NONCE_SIZE, POINT_SIZE, TAG_SIZE,
+};
+
+/// Size of an Ethereum address
+pub const ADDRESS_SIZE: usize = 20;
+
+/// Size of the ElGamal pair (c1, c2)
+pub const BLINDED_KEY_SIZE: usize = 2 * POINT_SIZE;
+
+/// Size of a ChaCha20 keystream block
+const CHACHA_BLOCK_SIZE: usize = 64;
+
+/// Size of a Poly1305 block
+const POLY1305_BLOCK_SIZE: usize = 16;
+
+/// Domain separation for the key schedule, bump when the schedule changes
+fn kdf_info(suite: CipherSuite) -> &'static [u8] {
+    match suite {
+        CipherSuite::ChaCha20 => b"stock0/envelope/chacha20/v1",
+        CipherSuite::ChaCha20Poly1305 => b"stock0/envelope/chacha20-poly1305/v1",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    InvalidPoint,
+    InvalidScalar,
+    Format(stock0_format::Error),
+    /// Poly1305 tag mismatch: wrong key, wrong image hash or a tampered blob
+    Unauthenticated,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidPoint => write!(f, "invalid secp256k1 point"),
+            Error::InvalidScalar => write!(f, "invalid secp256k1 scalar"),
+            Error::Format(err) => write!(f, "{err}"),
+            Error::Unauthenticated => write!(f, "blob authentication failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<stock0_format::Error> for Error {
+    fn from(err: stock0_format::Error) -> Self {
+        Error::Format(err)
+    }
+}
+
+/// Private inputs of the envelope encryption
+pub struct SealingKeys {
+    /// Buyer's public key `H` (compressed SEC1)
+    pub public_key_h: [u8; POINT_SIZE],
+    /// Session scalar `s` (big endian)
+    pub session_scalar: [u8; 32],
+    /// ElGamal ephemeral scalar `r` (big endian)
+    pub ephemeral_scalar: [u8; 32],
+    pub delivery_nonce: [u8; NONCE_SIZE],
+}
+
+/// Symmetric key material derived from the session point
+pub struct SessionKeys {
+    pub key: [u8; 32],
+    pub nonce: [u8; 12],
+}
+
+impl SessionKeys {
+    pub fn derive(
+        suite: CipherSuite,
+        session_point: &AffinePoint,
+        delivery_nonce: &[u8; NONCE_SIZE],
+    ) -> Self {
+        let hkdf = Hkdf::<Sha256>::new(Some(delivery_nonce), &session_point.to_bytes());
+        let mut okm = [0u8; 44];
+        hkdf.expand(kdf_info(suite), &mut okm)
+            .expect("44 bytes is a valid HKDF-SHA256 output length");
+
+        let mut keys = SessionKeys {
+            key: [0u8; 32],
+            nonce: [0u8; 12],
+        };
+        keys.key.copy_from_slice(&okm[..32]);
+        keys.nonce.copy_from_slice(&okm[32..]);
+        keys
+    }
+
+    pub fn apply_keystream(&self, data: &mut [u8]) {
+        let mut cipher = ChaCha20::new(&self.key.into(), &self.nonce.into());
+        cipher.apply_keystream(data);
+    }
+
+    pub fn encrypt_in_place(&self, associated_data: &[u8], data: &mut [u8]) -> [u8; TAG_SIZE] {
+        let cipher = ChaCha20Poly1305::new(&self.key.into());
+        let tag = cipher
+            .encrypt_in_place_detached(&self.nonce.into(), associated_data, data)
+            .expect("content fits into a single ChaCha20-Poly1305 message");
+        tag.into()
+    }
+
+    pub fn decrypt_in_place(
+        &self,
+        associated_data: &[u8],
+        data: &mut [u8],
+        tag: &[u8; TAG_SIZE],
+    ) -> Result<(), Error> {
+        let cipher = ChaCha20Poly1305::new(&self.key.into());
+        cipher
+            .decrypt_in_place_detached(&self.nonce.into(), associated_data, data, &Tag::from(*tag))
+            .map_err(|_| Error::Unauthenticated)
+    }
+}
+
+/// Encrypts the image for the owner of `keys.public_key_h`, returns the blob.
+/// Image hash is only used by the AEAD suite (as associated data).
+pub fn seal(
+    image_bytes: &[u8],
+    image_hash: &[u8],
+    mime_type: &str,
+    suite: CipherSuite,
+    keys: &SealingKeys,
+) -> Result<Vec<u8>, Error> {
+    let (mut sealer, mut blob) =
+        Sealer::new(image_bytes.len() as u64, image_hash, mime_type, suite, keys)?;
+    let header_len = blob.len();
+    blob.reserve(image_bytes.len() + suite.overhead());
+    blob.extend_from_slice(image_bytes);
+
+    // Encrypt image using symmetric encryption
+    sealer.update(&mut blob[header_len..]);
+    blob.extend_from_slice(&sealer.finalize());
+    Ok(blob)
+}
+
+/// Incremental [`seal`]: the blob is the header returned by [`Sealer::new`], followed by
+/// the chunks encrypted with [`Sealer::update`] and the bytes returned by [`Sealer::finalize`].
+///
+/// The original length and the image hash (AEAD suite) have to be known upfront,
+/// the caller is responsible for checking that the content matches them.
+pub struct Sealer {
+    cipher: ChaCha20,
+    /// Poly1305 state, AEAD suite only
+    mac: Option<Poly1305>,
+    associated_data_len: u64,
+    ciphertext_len: u64,
+    /// Ciphertext that does not fill a Poly1305 block yet
+    pending: Vec<u8>,
+}
+
+impl Sealer {
+    /// Wraps the session key for the buyer, returns the sealer and the serialized header
+    pub fn new(
+        original_len: u64,
+        image_hash: &[u8],
+        mime_type: &str,
+        suite: CipherSuite,
+        keys: &SealingKeys,
+    ) -> Result<(Self, Vec<u8>), Error> {
+        let h = parse_point(&keys.public_key_h)?;
+        let s = parse_scalar(&keys.session_scalar)?;
+        let r = parse_scalar(&keys.ephemeral_scalar)?;
+
+        // Blind session key (ElGamal): c1 = r * G, c2 = S + r * H
+        let session_point = AffinePoint::GENERATOR.mul(s).to_affine();
+        let c1 = AffinePoint::GENERATOR.mul(r).to_affine();
+        let c2 = (h.mul(r) + session_point).to_affine();
+
+        let header = Header {
+            version: stock0_format::VERSION,
+            cipher_suite: suite,
+            key_wrap: KeyWrap::ElGamalSecp256k1,
+            nonce: keys.delivery_nonce,
+            original_len,
+            mime_type: mime_type.to_string(),
+            wrapped_key: [c1.to_bytes(), c2.to_bytes()].concat(),
+        };
+
+        let session_keys = SessionKeys::derive(suite, &session_point, &keys.delivery_nonce);
+        let cipher = ChaCha20::new(&session_keys.key.into(), &session_keys.nonce.into());
+        let mut sealer = Sealer {
+            cipher,
+            mac: None,
+            associated_data_len: 0,
+            ciphertext_len: 0,
+            pending: Vec::with_capacity(POLY1305_BLOCK_SIZE),
+        };
+        if suite == CipherSuite::ChaCha20Poly1305 {
+            // Same construction as ChaCha20Poly1305 (RFC 8439): the first keystream block
+            // is the Poly1305 key, the content is encrypted starting from the second one
+            let mut mac_key = poly1305::Key::default();
+            sealer.cipher.apply_keystream(&mut mac_key);
+            sealer.cipher.seek(CHACHA_BLOCK_SIZE as u64);
+
+            let associated_data = [image_hash, &keys.public_key_h].concat();
+            let mut mac = Poly1305::new(&mac_key);
+            mac.update_padded(&associated_data);
+            sealer.mac = Some(mac);
+            sealer.associated_data_len = associated_data.len() as u64;
+        }
+        Ok((sealer, header.to_bytes()?))
+    }
+
+    /// Encrypts the next chunk of the content in place
+    pub fn update(&mut self, chunk: &mut [u8]) {
+        self.cipher.apply_keystream(chunk);
+
+        let Some(mac) = &mut self.mac else {
+            return;
+        };
+        self.ciphertext_len += chunk.len() as u64;
+
+        // Poly1305 pads every update, so only whole blocks are fed until the end
+        let mut chunk: &[u8] = chunk;
+        if !self.pending.is_empty() {
+            let len = (POLY1305_BLOCK_SIZE - self.pending.len()).min(chunk.len());
+            self.pending.extend_from_slice(&chunk[..len]);
+            chunk = &chunk[len..];
+            if self.pending.len() == POLY1305_BLOCK_SIZE {
+                mac.update_padded(&self.pending);
+                self.pending.clear();
+            }
+        }
+        let whole_blocks = chunk.len() - chunk.len() % POLY1305_BLOCK_SIZE;
+        mac.update_padded(&chunk[..whole_blocks]);
+        self.pending.extend_from_slice(&chunk[whole_blocks..]);
+    }
+
+    /// Returns the tail of the blob (the tag for the AEAD suite)
+    pub fn finalize(self) -> Vec<u8> {
+        let Some(mut mac) = self.mac else {
+            return Vec::new();
+        };
+        mac.update_padded(&self.pending);
+
+        let mut lengths = [0u8; POLY1305_BLOCK_SIZE];
+        lengths[..8].copy_from_slice(&self.associated_data_len.to_le_bytes());
+        lengths[8..].copy_from_slice(&self.ciphertext_len.to_le_bytes());
+        mac.update_padded(&lengths);
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+/// Recovers the session key with the buyer's secret key and decrypts the image.
+/// Image hash (from the journal) is only used by the AEAD suite (as associated data).
+pub fn open(
+    blob: &[u8],
+    secret_key_x: &[u8; 32],
+    image_hash: &[u8],
+) -> Result<(Header, Vec<u8>), Error> {
+    let (header, ciphertext) = Header::parse(blob)?;
+    let x = parse_scalar(secret_key_x)?;
+
+    let (c1, c2) = match header.key_wrap {
+        KeyWrap::ElGamalSecp256k1 => {
+            if header.wrapped_key.len() != BLINDED_KEY_SIZE {
+                return Err(Error::InvalidPoint);
+            }
+            (
+                parse_point(&header.wrapped_key[..POINT_SIZE])?,
+                parse_point(&header.wrapped_key[POINT_SIZE..])?,
+            )
+        }
+    };
+
+    // S = c2 - x * c1
+    let session_point = (ProjectivePoint::from(c2) - c1.mul(x)).to_affine();
+    let session_keys = SessionKeys::derive(header.cipher_suite, &session_point, &header.nonce);
+
+    let image_bytes = match header.cipher_suite {
+        CipherSuite::ChaCha20 => {
+            let mut image_bytes = ciphertext.to_vec();
+            session_keys.apply_keystream(&mut image_bytes);
+            image_bytes
+        }
+        CipherSuite::ChaCha20Poly1305 => {
+            let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - TAG_SIZE);
+            let public_key_h = AffinePoint::GENERATOR.mul(x).to_affine().to_bytes();
+            let associated_data = [image_hash, &public_key_h].concat();
+
+            let mut image_bytes = ciphertext.to_vec();
+            session_keys.decrypt_in_place(
+                &associated_data,
+                &mut image_bytes,
+                tag.try_into().expect("slice length is TAG_SIZE"),
+            )?;
+            image_bytes
+        }
+    };
+    Ok((header, image_bytes))
+}
+
+/// Ethereum address of the key owner: last 20 bytes of keccak256 of the uncompressed point
+pub fn ethereum_address(public_key: &[u8]) -> Result<[u8; ADDRESS_SIZE], Error> {
+    let point = parse_point(public_key)?.to_encoded_point(false);
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+
+    let mut address = [0u8; ADDRESS_SIZE];
+    address.copy_from_slice(&hash[32 - ADDRESS_SIZE..]);
+    Ok(address)
+}
+
+pub fn sha256(bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::default();
+    hasher.update(bytes);
+    hasher.finalize_reset().into()
+}
+
+fn parse_scalar(bytes: &[u8; 32]) -> Result<Scalar, Error> {
+    Option::from(Scalar::from_repr((*bytes).into())).ok_or(Error::InvalidScalar)
+}
+
+fn parse_point(bytes: &[u8]) -> Result<AffinePoint, Error> {
+    let public_key =
+        PublicKey::<Secp256k1>::from_sec1_bytes(bytes).map_err(|_| Error::InvalidPoint)?;
+    Ok(*public_key.as_affine())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Chunk sizes around the ChaCha20 block, the Poly1305 block and the 16KB guest chunk
+    const CHUNK_SIZES: &[usize] = &[1, 15, 16, 17, 63, 64, 65, 127, 16383, 16384, 16385];
+
+    fn keys() -> SealingKeys {
+        let x = Scalar::from(7u64);
+        SealingKeys {
+            public_key_h: AffinePoint::GENERATOR.mul(x).to_affine().to_bytes().into(),
+            session_scalar: Scalar::from(11u64).to_repr().into(),
+            ephemeral_scalar: Scalar::from(13u64).to_repr().into(),
+            delivery_nonce: [5u8; NONCE_SIZE],
+        }
+    }
+
+    fn secret_key() -> [u8; 32] {
+        Scalar::from(7u64).to_repr().into()
+    }
+
+    fn image(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    /// Blob sealed chunk by chunk
+    fn seal_chunked(
+        image_bytes: &[u8],
+        image_hash: &[u8],
+        suite: CipherSuite,
+        chunk_size: usize,
+    ) -> Vec<u8> {
+        let (mut sealer, mut blob) = Sealer::new(
+            image_bytes.len() as u64,
+            image_hash,
+            "image/tiff",
+            suite,
+            &keys(),
+        )
+        .unwrap();
+        for chunk in image_bytes.chunks(chunk_size) {
+            let mut chunk = chunk.to_vec();
+            sealer.update(&mut chunk);
+            blob.extend_from_slice(&chunk);
+        }
+        blob.extend_from_slice(&sealer.finalize());
+        blob
+    }
+
+    /// Ciphertext (and tag) computed with the reference implementations
+    fn reference_body(image_bytes: &[u8], image_hash: &[u8], suite: CipherSuite) -> Vec<u8> {
+        let keys = keys();
+        let session_point = AffinePoint::GENERATOR
+            .mul(parse_scalar(&keys.session_scalar).unwrap())
+            .to_affine();
+        let session_keys = SessionKeys::derive(suite, &session_point, &keys.delivery_nonce);
+
+        let mut body = image_bytes.to_vec();
+        match suite {
+            CipherSuite::ChaCha20 => {
+                let mut cipher =
+                    ChaCha20::new(&session_keys.key.into(), &session_keys.nonce.into());
+                cipher.apply_keystream(&mut body);
+            }
+            CipherSuite::ChaCha20Poly1305 => {
+                let associated_data = [image_hash, &keys.public_key_h].concat();
+                let cipher = ChaCha20Poly1305::new(&session_keys.key.into());
+                let tag = cipher
+                    .encrypt_in_place_detached(
+                        &session_keys.nonce.into(),
+                        &associated_data,
+                        &mut body,
+                    )
+                    .unwrap();
+                body.extend_from_slice(&tag);
+            }
+        }
+        body
+    }
+
+    #[test]
+    fn sealer_matches_reference() {
+        for suite in [CipherSuite::ChaCha20, CipherSuite::ChaCha20Poly1305] {
+            for len in [0, 1, 16, 64, 100, 16384, 40_000] {
+                let image_bytes = image(len);
+                let image_hash = sha256(&image_bytes);
+
+                let blob = seal(&image_bytes, &image_hash, "image/tiff", suite, &keys()).unwrap();
+                let (header, body) = Header::parse(&blob).unwrap();
+                assert_eq!(body, reference_body(&image_bytes, &image_hash, suite));
+                assert_eq!(header.original_len, len as u64);
+
+                for &chunk_size in CHUNK_SIZES {
+                    assert_eq!(
+                        seal_chunked(&image_bytes, &image_hash, suite, chunk_size),
+                        blob,
+                        "{suite:?}, {len} bytes in chunks of {chunk_size}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sealer_uneven_chunks() {
+        // Pending Poly1305 bytes carried over several updates
+        let image_bytes = image(50_000);
+        let image_hash = sha256(&image_bytes);
+        let suite = CipherSuite::ChaCha20Poly1305;
+
+        let (mut sealer, mut blob) = Sealer::new(
+            image_bytes.len() as u64,
+            &image_hash,
+            "image/tiff",
+            suite,
+            &keys(),
+        )
+        .unwrap();
+        let mut rest = &image_bytes[..];
+        for size in [3, 5, 7, 1, 16384, 9, 64, 33].iter().cycle() {
+            let (chunk, tail) = rest.split_at((*size).min(rest.len()));
+            let mut chunk = chunk.to_vec();
+            sealer.update(&mut chunk);
+            blob.extend_from_slice(&chunk);
+            rest = tail;
+            if rest.is_empty() {
+                break;
+            }
+        }
+        blob.extend_from_slice(&sealer.finalize());
+
+        let (_, body) = Header::parse(&blob).unwrap();
+        assert_eq!(body, reference_body(&image_bytes, &image_hash, suite));
+    }
+
+    #[test]
+    fn opens_sealed_blob() {
+        for suite in [CipherSuite::ChaCha20, CipherSuite::ChaCha20Poly1305] {
+            let image_bytes = image(1000);
+            let image_hash = sha256(&image_bytes);
+            let blob = seal(&image_bytes, &image_hash, "image/tiff", suite, &keys()).unwrap();
+
+            let (header, opened) = open(&blob, &secret_key(), &image_hash).unwrap();
+            assert_eq!(opened, image_bytes);
+            assert_eq!(header.cipher_suite, suite);
+            assert_eq!(header.mime_type, "image/tiff");
+        }
+
+        // AEAD binds the image hash
+        let image_bytes = image(1000);
+        let image_hash = sha256(&image_bytes);
+        let suite = CipherSuite::ChaCha20Poly1305;
+        let mut blob = seal(&image_bytes, &image_hash, "image/tiff", suite, &keys()).unwrap();
+        assert_eq!(
+            open(&blob, &secret_key(), &[0u8; 32]).unwrap_err(),
+            Error::Unauthenticated
+        );
+        let last = blob.len() - 1;
+        blob[last] ^= 1;
+        assert_eq!(
+            open(&blob, &secret_key(), &image_hash).unwrap_err(),
+            Error::Unauthenticated
+        );
+    }
+}
@@ -1,19 +1,17 @@
 use celestia_types::nmt::{MerkleHash, Namespace, NamespaceProof, NamespacedHashExt};
-use celestia_types::InfoByte;
-use chacha20::cipher::{NewCipher, StreamCipher};
-use chacha20::ChaCha20;
-use k256::elliptic_curve::{PrimeField, PublicKey, group::GroupEncoding};
-use k256::{AffinePoint, Scalar, Secp256k1};
 use nmt_rs::simple_merkle::proof::Proof;
 use nmt_rs::{NamespacedHash, TmSha2Hasher};
 use risc0_zkvm::guest::env;
-use risc0_zkvm::sha::rust_crypto::{Digest as _, Sha256};
-use std::io::{Read, Cursor};
-use std::ops::Mul;
-use bytes::{Buf, BufMut, BytesMut};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use stock0_celestia::{
+    BlobCommitter, ShareSplitter, ShareVersion, NAMESPACE_SIZE, SHARE_SIZE, SIGNER_SIZE,
+};
+use stock0_crypto::{CipherSuite, Sealer, SealingKeys, NONCE_SIZE};
+use stock0_journal::{DeliveryJournal, Journal};
 
-const CHACHA_STATIC_NONCE: &[u8; 12] = b"bakingbaddev";
-const NAMESPACE: &[u8] = &[1, 2, 3, 4, 5];
+/// Image bytes read from the host at once
+const CHUNK_SIZE: usize = 16 * 1024;
 
 fn main() {
     // read the data root
@@ -39,56 +37,107 @@ fn main() {
     let mut public_key_h = [0u8; 33];
     env::read_slice(&mut public_key_h);
 
-    // Read random scalar (SEC1)
-    let mut random_scalar_y = [0u8; 32];
-    env::read_slice(&mut random_scalar_y);
-
-    // Read raw image data
-    let mut image_bytes = Vec::<u8>::new();
-    env::stdin().read_to_end(&mut image_bytes).unwrap();
-
-    // Load the keys
-    let session_key = chacha20::Key::from_slice(&random_scalar_y);
-    let public_key =
-        PublicKey::<Secp256k1>::from_sec1_bytes(&public_key_h).expect("parse public point");
-
-    // Blind session key
-    let h = public_key.as_affine();
-    let y = Scalar::from_repr(random_scalar_y.into()).expect("parse scalar");
-    let c1 = AffinePoint::GENERATOR.mul(y).to_affine();
-    let c2 = h.mul(y).to_affine();
-
-    // This is "encrypted" encryption key
-    let blinded_key = [c1.to_bytes().to_vec(), c2.to_bytes().to_vec()].concat();
-
-    // Compute original image hash
-    let image_hash = sha256(&image_bytes);
-
-    // Encrypt image using symmetric encryption
-    let nonce = chacha20::Nonce::from_slice(CHACHA_STATIC_NONCE);
-    let mut cipher = ChaCha20::new(session_key, nonce);
-
-    let mut encrypted_image = image_bytes;
-    cipher.try_apply_keystream(&mut encrypted_image).unwrap();
-
-    // Construct blob
-    let blob = [blinded_key, encrypted_image].concat();
-    let namespace = Namespace::new_v0(NAMESPACE).expect("Invalid namespace");
-
-    // Split into shares
-    let shares = split_blob_to_shares(namespace, &blob);
-
-    // We have one NMT range proof for each row spanned by the blob
-    // Verify that the blob's shares go into the respective row roots
-    let mut start = 0;
-    for i in 0..num_rows {
-        let proof = &proofs[i as usize];
-        let root = &row_roots[i as usize];
-        let end = start + (proof.end_idx() as usize - proof.start_idx() as usize);
-        proof.verify_range(root, &shares[start..end], namespace.into()).unwrap();
-        start = end;
+    // Read random session scalar (big endian)
+    let mut random_scalar_s = [0u8; 32];
+    env::read_slice(&mut random_scalar_s);
+
+    // Read random ephemeral scalar for ElGamal (big endian)
+    let mut random_scalar_r = [0u8; 32];
+    env::read_slice(&mut random_scalar_r);
+
+    // Read per-delivery nonce
+    let mut delivery_nonce = [0u8; NONCE_SIZE];
+    env::read_slice(&mut delivery_nonce);
+
+    // Read cipher suite id
+    let mut cipher_suite = [0u8; 1];
+    env::read_slice(&mut cipher_suite);
+    let cipher_suite = CipherSuite::from_u8(cipher_suite[0]).expect("Unknown cipher suite");
+
+    // Read Celestia namespace of the blob
+    let mut namespace = [0u8; NAMESPACE_SIZE];
+    env::read_slice(&mut namespace);
+    let namespace = stock0_celestia::parse_namespace(&namespace).expect("Invalid namespace");
+
+    // Read share version and signer address (zeroed for share version 0)
+    let mut share_version = [0u8; 1];
+    env::read_slice(&mut share_version);
+    let mut signer = [0u8; SIGNER_SIZE];
+    env::read_slice(&mut signer);
+    let share_version = ShareVersion::new(share_version[0], signer).expect("Invalid share version");
+
+    // Read image length and hash, the image itself is streamed in chunks below
+    let mut image_len = [0u8; 8];
+    env::read_slice(&mut image_len);
+    let image_len = usize::try_from(u64::from_be_bytes(image_len)).expect("Image too large");
+    let mut image_hash = [0u8; 32];
+    env::read_slice(&mut image_hash);
+
+    let keys = SealingKeys {
+        public_key_h,
+        session_scalar: random_scalar_s,
+        ephemeral_scalar: random_scalar_r,
+        delivery_nonce,
+    };
+
+    // The MIME type is sniffed from the first chunk
+    let mut stdin = env::stdin();
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    let first_chunk_len = CHUNK_SIZE.min(image_len);
+    stdin.read_exact(&mut chunk[..first_chunk_len]).unwrap();
+    let mime_type = stock0_crypto::sniff_mime_type(&chunk[..first_chunk_len]);
+
+    // Blind the session key, the blob starts with the envelope header
+    let (mut sealer, header) =
+        Sealer::new(image_len as u64, &image_hash, mime_type, cipher_suite, &keys)
+            .expect("Failed to encrypt image");
+    let blob_len = header.len() + image_len + cipher_suite.overhead();
+
+    // Encrypt the image chunk by chunk, split the blob into shares as it goes and
+    // check every row of shares against its NMT proof as soon as it is complete
+    let mut splitter = ShareSplitter::new(namespace, share_version, blob_len)
+        .expect("Failed to split blob to shares");
+    let mut rows = RowVerifier {
+        namespace,
+        row_roots: &row_roots,
+        proofs: &proofs,
+        committer: BlobCommitter::new(namespace, splitter.num_shares()),
+        row: 0,
+        shares: Vec::new(),
+    };
+    let mut hasher = Sha256::new();
+
+    splitter.push(&header, &mut rows.shares).unwrap();
+    rows.verify_complete_rows();
+
+    let mut remaining = image_len;
+    let mut chunk_len = first_chunk_len;
+    loop {
+        let data = &mut chunk[..chunk_len];
+        hasher.update(&*data);
+        sealer.update(data);
+        splitter.push(data, &mut rows.shares).unwrap();
+        rows.verify_complete_rows();
+
+        remaining -= chunk_len;
+        if remaining == 0 {
+            break;
+        }
+        chunk_len = CHUNK_SIZE.min(remaining);
+        stdin.read_exact(&mut chunk[..chunk_len]).unwrap();
     }
 
+    splitter.push(&sealer.finalize(), &mut rows.shares).unwrap();
+    splitter.finish(&mut rows.shares).unwrap();
+    rows.verify_complete_rows();
+
+    // Check original image hash
+    let computed_hash: [u8; 32] = hasher.finalize().into();
+    assert_eq!(computed_hash, image_hash, "Image does not match its hash");
+
+    assert_eq!(rows.row, proofs.len(), "Not all of the blob shares are proven");
+    assert!(rows.shares.is_empty(), "Not all of the blob shares are proven");
+
     // Verify the row-inclusion range proof
     let tm_hasher = TmSha2Hasher {};
     let blob_row_root_hashes: Vec<[u8; 32]> = row_roots
@@ -103,66 +152,57 @@ fn main() {
         &blob_row_root_hashes,
     ).unwrap();
 
-    // Write original image & blob hashes as well as the receiver's public key to the journal
-    env::commit_slice(&[image_hash, data_root.to_vec(), public_key_h.to_vec()].concat());
+    // Calculate blob commitment
+    let blob_commitment = rows.committer.finalize().expect("Failed to create commitment");
+
+    // Derive buyer's Ethereum address, so that the delivery can be matched against the purchase
+    let buyer_address =
+        stock0_crypto::ethereum_address(&public_key_h).expect("Invalid buyer's public key");
+
+    // Write original image hash, data root, the receiver's key & address, the nonce,
+    // and the blob commitment, namespace & share version to the journal
+    let journal = DeliveryJournal {
+        image_hash,
+        data_root,
+        public_key_h,
+        buyer_address,
+        delivery_nonce,
+        blob_commitment,
+        namespace: stock0_celestia::namespace_bytes(&namespace),
+        share_version: share_version.as_u8(),
+        signer: share_version.signer(),
+    };
+    env::commit_slice(&journal.to_bytes());
 }
 
-fn sha256(bytes: &[u8]) -> Vec<u8> {
-    let mut hasher = Sha256::default();
-    hasher.update(&bytes);
-    hasher.finalize_reset().to_vec()
-}
-
-fn split_blob_to_shares(
+/// Checks the shares row by row against the NMT range proofs, and feeds them to the commitment
+struct RowVerifier<'a> {
     namespace: Namespace,
-    blob_data: &[u8],
-) -> Vec<[u8; 512]> {
-    let mut shares = Vec::new();
-    let mut cursor = Cursor::new(blob_data);
-
-    while cursor.has_remaining() {
-        let share = build_sparse_share_v0(namespace, &mut cursor);
-        shares.push(share);
-    }
-    shares
+    row_roots: &'a [NamespacedHash<29>],
+    proofs: &'a [NamespaceProof],
+    committer: BlobCommitter,
+    /// Index of the next row to verify
+    row: usize,
+    /// Shares of the row in progress
+    shares: Vec<[u8; SHARE_SIZE]>,
 }
 
-/// Build a sparse share from a cursor over data
-fn build_sparse_share_v0(
-    namespace: Namespace,
-    data: &mut Cursor<impl AsRef<[u8]>>,
-) -> [u8; 512] {
-    let is_first_share = data.position() == 0;
-    let data_len = cursor_inner_length(data);
-    let mut bytes = BytesMut::with_capacity(512);
-
-    // Write the namespace
-    bytes.put_slice(namespace.as_bytes());
-    // Write the info byte
-    let info_byte = InfoByte::new(0, is_first_share).unwrap();
-    bytes.put_u8(info_byte.as_u8());
-
-    // If this share is first in the sequence, write the bytes len of the sequence
-    if is_first_share {
-        let data_len = data_len
-            .try_into()
-            .unwrap();
-        bytes.put_u32(data_len);
+impl RowVerifier<'_> {
+    fn verify_complete_rows(&mut self) {
+        while self.row < self.proofs.len() {
+            let proof = &self.proofs[self.row];
+            let row_len = proof.end_idx() as usize - proof.start_idx() as usize;
+            if self.shares.len() < row_len {
+                return;
+            }
+            proof
+                .verify_range(&self.row_roots[self.row], &self.shares[..row_len], self.namespace.into())
+                .unwrap();
+            for share in self.shares.drain(..row_len) {
+                self.committer.push(&share).expect("Failed to create commitment");
+            }
+            self.row += 1;
+        }
+        assert!(self.shares.is_empty(), "Blob has more shares than proven");
     }
-
-    // Calculate amount of bytes to read
-    let current_size = bytes.len();
-    let available_space = 512 - current_size;
-    let read_amount = available_space.min(data.remaining());
-
-    // Resize to share size with 0 padding
-    bytes.resize(512, 0);
-    // Read the share data
-    data.copy_to_slice(&mut bytes[current_size..current_size + read_amount]);
-
-    bytes.to_vec().try_into().unwrap()
-}
-
-fn cursor_inner_length(cursor: &Cursor<impl AsRef<[u8]>>) -> usize {
-    cursor.get_ref().as_ref().len()
 }
@@ -1,70 +1,85 @@
-use celestia_types::nmt::Namespace;
-use celestia_types::Commitment;
-use chacha20::cipher::{NewCipher, StreamCipher};
-use chacha20::ChaCha20;
-use k256::elliptic_curve::{PrimeField, PublicKey, group::GroupEncoding};
-use k256::{AffinePoint, Scalar, Secp256k1};
 use risc0_zkvm::guest::env;
-use risc0_zkvm::sha::rust_crypto::{Digest as _, Sha256};
 use std::io::Read;
-use std::ops::Mul;
-
-const CHACHA_STATIC_NONCE: &[u8; 12] = b"bakingbaddev";
-const CELESTIA_NAMESPACE: &[u8; 10] = b"bakingbad0";
+use stock0_celestia::{ShareVersion, NAMESPACE_SIZE, SIGNER_SIZE};
+use stock0_crypto::{CipherSuite, SealingKeys, NONCE_SIZE};
+use stock0_journal::{EnvelopeJournal, Journal};
 
 fn main() {
     // Read receiver's public key (SEC1)
     let mut public_key_h = [0u8; 33];
     env::stdin().read(&mut public_key_h).unwrap();
 
-    // Read random scalar (SEC1)
-    let mut random_scalar_y = [0u8; 32];
-    env::stdin().read(&mut random_scalar_y).unwrap();
+    // Read random session scalar (big endian)
+    let mut random_scalar_s = [0u8; 32];
+    env::stdin().read(&mut random_scalar_s).unwrap();
+
+    // Read random ephemeral scalar for ElGamal (big endian)
+    let mut random_scalar_r = [0u8; 32];
+    env::stdin().read(&mut random_scalar_r).unwrap();
 
-    let session_key = chacha20::Key::from_slice(&random_scalar_y);
+    // Read per-delivery nonce
+    let mut delivery_nonce = [0u8; NONCE_SIZE];
+    env::stdin().read(&mut delivery_nonce).unwrap();
 
-    let public_key =
-        PublicKey::<Secp256k1>::from_sec1_bytes(&public_key_h).expect("parse public point");
+    // Read cipher suite id
+    let mut cipher_suite = [0u8; 1];
+    env::stdin().read(&mut cipher_suite).unwrap();
+    let cipher_suite = CipherSuite::from_u8(cipher_suite[0]).expect("Unknown cipher suite");
 
-    // Blind session key
-    let h = public_key.as_affine();
-    let y = Scalar::from_repr(random_scalar_y.into()).expect("parse scalar");
-    let c1 = AffinePoint::GENERATOR.mul(y).to_affine();
-    let c2 = h.mul(y).to_affine();
+    // Read Celestia namespace of the blob
+    let mut namespace = [0u8; NAMESPACE_SIZE];
+    env::stdin().read(&mut namespace).unwrap();
+    let namespace = stock0_celestia::parse_namespace(&namespace).expect("Invalid namespace");
 
-    // This is "encrypted" encryption key
-    let blinded_key = [c1.to_bytes().to_vec(), c2.to_bytes().to_vec()].concat();
+    // Read share version and signer address (zeroed for share version 0)
+    let mut share_version = [0u8; 1];
+    env::stdin().read(&mut share_version).unwrap();
+    let mut signer = [0u8; SIGNER_SIZE];
+    env::stdin().read(&mut signer).unwrap();
+    let share_version = ShareVersion::new(share_version[0], signer).expect("Invalid share version");
 
     // Read raw image data
     let mut image_bytes = Vec::<u8>::new();
     env::stdin().read_to_end(&mut image_bytes).unwrap();
 
     // Compute original image hash
-    let image_hash = sha256(&image_bytes);
-
-    // Encrypt image using symmetric encryption
-    let nonce = chacha20::Nonce::from_slice(CHACHA_STATIC_NONCE);
-    let mut cipher = ChaCha20::new(session_key, nonce);
-
-    let mut encrypted_image = image_bytes;
-    cipher.try_apply_keystream(&mut encrypted_image).unwrap();
-
-    // Construct blob
-    let blob = [blinded_key, encrypted_image].concat();
+    let image_hash = stock0_crypto::sha256(&image_bytes);
+
+    // Encrypt image and blind the session key, this is our blob
+    let keys = SealingKeys {
+        public_key_h,
+        session_scalar: random_scalar_s,
+        ephemeral_scalar: random_scalar_r,
+        delivery_nonce,
+    };
+    let mime_type = stock0_crypto::sniff_mime_type(&image_bytes);
+    let blob = stock0_crypto::seal(&image_bytes, &image_hash, mime_type, cipher_suite, &keys)
+        .expect("Failed to encrypt image");
 
     // Calculate blob commitment
-    let blob_commitment = Commitment::from_blob(Namespace::const_v0(*CELESTIA_NAMESPACE), 0, &blob)
+    let shares = stock0_celestia::split_blob_to_shares(namespace, share_version, &blob)
+        .expect("Failed to split blob to shares");
+    let blob_commitment = stock0_celestia::blob_commitment(namespace, &shares)
         .expect("Failed to create commitment");
 
-    // Write original image & blob hashes as well as the receiver's public key to the journal
-    env::commit_slice(&[image_hash, blob_commitment.0.to_vec(), public_key_h.to_vec()].concat());
+    // Derive buyer's Ethereum address, so that the delivery can be matched against the purchase
+    let buyer_address =
+        stock0_crypto::ethereum_address(&public_key_h).expect("Invalid buyer's public key");
+
+    // Write original image & blob hashes, the receiver's key & address, the nonce,
+    // and the namespace & share version to the journal
+    let journal = EnvelopeJournal {
+        image_hash,
+        blob_commitment,
+        public_key_h,
+        buyer_address,
+        delivery_nonce,
+        namespace: stock0_celestia::namespace_bytes(&namespace),
+        share_version: share_version.as_u8(),
+        signer: share_version.signer(),
+    };
+    env::commit_slice(&journal.to_bytes());
 
     // Write blob to the stdout
     env::write_slice(&blob);
 }
-
-fn sha256(bytes: &[u8]) -> Vec<u8> {
-    let mut hasher = Sha256::default();
-    hasher.update(&bytes);
-    hasher.finalize_reset().to_vec()
-}
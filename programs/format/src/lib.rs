@@ -0,0 +1,333 @@
+//! Binary envelope format of the delivered blobs, shared by the host and the guest programs.
+//!
+//! A blob is a self-describing header followed by the ciphertext (and the AEAD tag if any).
+//! All integers are big endian.
+//!
+//! | Field           | Size | Description                                      |
+//! |-----------------|------|--------------------------------------------------|
+//! | magic           | 4    | `S0EV`                                           |
+//! | version         | 1    | format version, currently `1`                    |
+//! | cipher suite    | 1    | see [`CipherSuite`]                              |
+//! | key wrap scheme | 1    | see [`KeyWrap`]                                  |
+//! | nonce           | 12   | per-delivery nonce                               |
+//! | original length | 8    | plaintext length in bytes                        |
+//! | MIME type len   | 1    |                                                  |
+//! | MIME type       | var  | ASCII, e.g. `image/tiff`                         |
+//! | wrapped key len | 2    |                                                  |
+//! | wrapped key     | var  | for ElGamal: `c1 \|\| c2` (compressed SEC1)      |
+//!
+//! Blobs created before the header was introduced (`c1 || c2 || ciphertext`) are rejected: their
+//! session key is not recoverable from the wrapped key, so there is nothing to decrypt them with.
+
+use std::fmt;
+
+pub const MAGIC: &[u8; 4] = b"S0EV";
+
+/// Current format version
+pub const VERSION: u8 = 1;
+
+/// Size of the per-delivery nonce
+pub const NONCE_SIZE: usize = 12;
+
+/// Size of the Poly1305 tag
+pub const TAG_SIZE: usize = 16;
+
+/// Size of a compressed SEC1 point
+pub const POINT_SIZE: usize = 33;
+
+/// Fallback content type
+pub const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    Truncated,
+    MissingMagic,
+    WrappedKeyTooLong,
+    UnsupportedVersion(u8),
+    UnknownCipherSuite(u8),
+    UnknownKeyWrap(u8),
+    UnknownImageFormat(u8),
+    InvalidMimeType,
+    LengthMismatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "blob is truncated"),
+            Error::MissingMagic => write!(f, "blob does not start with the envelope magic"),
+            Error::WrappedKeyTooLong => write!(f, "wrapped key must be up to 65535 bytes"),
+            Error::UnsupportedVersion(v) => write!(f, "unsupported envelope version {v}"),
+            Error::UnknownCipherSuite(id) => write!(f, "unknown cipher suite {id}"),
+            Error::UnknownKeyWrap(id) => write!(f, "unknown key wrap scheme {id}"),
+            Error::UnknownImageFormat(id) => write!(f, "unknown image format {id}"),
+            Error::InvalidMimeType => write!(f, "MIME type must be ASCII, up to 255 bytes"),
+            Error::LengthMismatch => write!(f, "ciphertext length does not match the header"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Symmetric cipher used to encrypt the content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CipherSuite {
+    /// Bare stream cipher, tampering goes unnoticed until the hash check
+    ChaCha20 = 0,
+    /// Authenticated encryption, the tag is appended to the ciphertext
+    ChaCha20Poly1305 = 1,
+}
+
+impl CipherSuite {
+    pub fn from_u8(id: u8) -> Result<Self, Error> {
+        match id {
+            0 => Ok(CipherSuite::ChaCha20),
+            1 => Ok(CipherSuite::ChaCha20Poly1305),
+            _ => Err(Error::UnknownCipherSuite(id)),
+        }
+    }
+
+    /// Bytes appended to the ciphertext
+    pub fn overhead(&self) -> usize {
+        match self {
+            CipherSuite::ChaCha20 => 0,
+            CipherSuite::ChaCha20Poly1305 => TAG_SIZE,
+        }
+    }
+}
+
+/// How the session key is encrypted for the buyer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum KeyWrap {
+    /// ElGamal over secp256k1, wrapped key is `c1 || c2`
+    ElGamalSecp256k1 = 1,
+}
+
+impl KeyWrap {
+    pub fn from_u8(id: u8) -> Result<Self, Error> {
+        match id {
+            1 => Ok(KeyWrap::ElGamalSecp256k1),
+            _ => Err(Error::UnknownKeyWrap(id)),
+        }
+    }
+}
+
+/// Image formats the thumbnail program decodes, the ID is committed in its journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ImageFormat {
+    Tiff = 1,
+    Png = 2,
+    Jpeg = 3,
+}
+
+impl ImageFormat {
+    pub fn from_u8(id: u8) -> Result<Self, Error> {
+        match id {
+            1 => Ok(ImageFormat::Tiff),
+            2 => Ok(ImageFormat::Png),
+            3 => Ok(ImageFormat::Jpeg),
+            _ => Err(Error::UnknownImageFormat(id)),
+        }
+    }
+
+    /// Detects the format from the magic bytes
+    pub fn sniff(content: &[u8]) -> Option<Self> {
+        if content.starts_with(b"II*\0") || content.starts_with(b"MM\0*") {
+            Some(ImageFormat::Tiff)
+        } else if content.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if content.starts_with(b"\xff\xd8\xff") {
+            Some(ImageFormat::Jpeg)
+        } else {
+            None
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ImageFormat::Tiff => "image/tiff",
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub version: u8,
+    pub cipher_suite: CipherSuite,
+    pub key_wrap: KeyWrap,
+    pub nonce: [u8; NONCE_SIZE],
+    pub original_len: u64,
+    pub mime_type: String,
+    pub wrapped_key: Vec<u8>,
+}
+
+impl Header {
+    /// Serializes the header (always in the current version)
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        if !self.mime_type.is_ascii() || self.mime_type.len() > u8::MAX as usize {
+            return Err(Error::InvalidMimeType);
+        }
+        let wrapped_key_len: u16 = self
+            .wrapped_key
+            .len()
+            .try_into()
+            .map_err(|_| Error::WrappedKeyTooLong)?;
+
+        let mut bytes = Vec::with_capacity(self.encoded_len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.cipher_suite as u8);
+        bytes.push(self.key_wrap as u8);
+        bytes.extend_from_slice(&self.nonce);
+        bytes.extend_from_slice(&self.original_len.to_be_bytes());
+        bytes.push(self.mime_type.len() as u8);
+        bytes.extend_from_slice(self.mime_type.as_bytes());
+        bytes.extend_from_slice(&wrapped_key_len.to_be_bytes());
+        bytes.extend_from_slice(&self.wrapped_key);
+        Ok(bytes)
+    }
+
+    /// Size of the serialized header
+    pub fn encoded_len(&self) -> usize {
+        MAGIC.len() + 3 + NONCE_SIZE + 8 + 1 + self.mime_type.len() + 2 + self.wrapped_key.len()
+    }
+
+    /// Parses the header, returns it along with the rest of the blob (ciphertext [|| tag]).
+    /// The ciphertext length is checked against the header.
+    pub fn parse(blob: &[u8]) -> Result<(Header, &[u8]), Error> {
+        let Some(blob) = blob.strip_prefix(MAGIC) else {
+            return Err(Error::MissingMagic);
+        };
+        let mut reader = Reader(blob);
+
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let cipher_suite = CipherSuite::from_u8(reader.u8()?)?;
+        let key_wrap = KeyWrap::from_u8(reader.u8()?)?;
+        let nonce = reader.take(NONCE_SIZE)?.try_into().unwrap();
+        let original_len = u64::from_be_bytes(reader.take(8)?.try_into().unwrap());
+
+        let mime_type_len = reader.u8()? as usize;
+        let mime_type = reader.take(mime_type_len)?;
+        if !mime_type.is_ascii() {
+            return Err(Error::InvalidMimeType);
+        }
+        let mime_type = String::from_utf8(mime_type.to_vec()).unwrap();
+
+        let wrapped_key_len = u16::from_be_bytes(reader.take(2)?.try_into().unwrap()) as usize;
+        let wrapped_key = reader.take(wrapped_key_len)?.to_vec();
+
+        let header = Header {
+            version,
+            cipher_suite,
+            key_wrap,
+            nonce,
+            original_len,
+            mime_type,
+            wrapped_key,
+        };
+
+        // Original length is untrusted, it must not overflow (or truncate on 32-bit targets)
+        let expected_len = usize::try_from(header.original_len)
+            .ok()
+            .and_then(|len| len.checked_add(header.cipher_suite.overhead()))
+            .ok_or(Error::LengthMismatch)?;
+        if reader.0.len() != expected_len {
+            return Err(Error::LengthMismatch);
+        }
+        Ok((header, reader.0))
+    }
+}
+
+/// Guesses content type from the magic bytes
+pub fn sniff_mime_type(content: &[u8]) -> &'static str {
+    if let Some(format) = ImageFormat::sniff(content) {
+        format.mime_type()
+    } else if content.len() >= 12 && &content[..4] == b"RIFF" && &content[8..12] == b"WEBP" {
+        "image/webp"
+    } else {
+        DEFAULT_MIME_TYPE
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::Truncated);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(original_len: u64) -> Header {
+        Header {
+            version: VERSION,
+            cipher_suite: CipherSuite::ChaCha20Poly1305,
+            key_wrap: KeyWrap::ElGamalSecp256k1,
+            nonce: [7; NONCE_SIZE],
+            original_len,
+            mime_type: "image/tiff".to_string(),
+            wrapped_key: vec![2; 2 * POINT_SIZE],
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let header = header(3);
+        let blob = [header.to_bytes().unwrap(), vec![1; 3 + TAG_SIZE]].concat();
+        let (parsed, body) = Header::parse(&blob).unwrap();
+        assert_eq!(parsed, header);
+        assert_eq!(body, &[1; 3 + TAG_SIZE]);
+        assert_eq!(header.encoded_len(), blob.len() - body.len());
+    }
+
+    #[test]
+    fn rejects_length_mismatch() {
+        let header = header(3);
+        let blob = [header.to_bytes().unwrap(), vec![1; 3]].concat();
+        assert_eq!(Header::parse(&blob), Err(Error::LengthMismatch));
+    }
+
+    #[test]
+    fn rejects_overflowing_length() {
+        // Would wrap to 0 with the tag added on 64-bit targets
+        for original_len in [u64::MAX - TAG_SIZE as u64 + 1, u64::MAX] {
+            let blob = header(original_len).to_bytes().unwrap();
+            assert_eq!(Header::parse(&blob), Err(Error::LengthMismatch));
+        }
+    }
+
+    #[test]
+    fn rejects_headerless_blob() {
+        let mut blob = vec![2; 2 * POINT_SIZE];
+        blob.extend_from_slice(b"ciphertext");
+        assert_eq!(Header::parse(&blob), Err(Error::MissingMagic));
+    }
+
+    #[test]
+    fn rejects_truncated_header() {
+        let blob = header(0).to_bytes().unwrap();
+        for len in MAGIC.len()..blob.len() {
+            assert_eq!(Header::parse(&blob[..len]), Err(Error::Truncated));
+        }
+    }
+}
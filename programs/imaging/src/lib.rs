@@ -0,0 +1,604 @@
+//! Image operations shared by the guest programs: decoding, resizing, preview effects,
+//! watermarking and the operations of a transformation pipeline.
+//!
+//! Everything is integer or software float arithmetic of the `image` crate, so the output is
+//! the same on the host and in the zkVM.
+
+use std::fmt;
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ExtendedColorType, ImageEncoder, Rgb, RgbImage, Rgba, RgbaImage};
+use sha2::{Digest, Sha256};
+use stock0_format::ImageFormat;
+use stock0_spec::{
+    ColorMode, Operation, PreviewEffect, PreviewParams, ResizeFilter, Rotation, ThumbnailFormat,
+    WatermarkKind, WatermarkSpec,
+};
+
+/// Font the text watermarks are rendered with, part of the programs so it is fixed by the image ID
+const FONT: &[u8] = include_bytes!("../assets/DejaVuSansMono-Bold.ttf");
+
+#[derive(Debug)]
+pub enum Error {
+    /// Neither TIFF, PNG nor JPEG
+    UnsupportedFormat,
+    Image(image::ImageError),
+    Spec(stock0_spec::Error),
+    /// Crop rectangle is not within the image
+    OutOfBounds,
+    /// Watermark text is empty, not UTF-8 or spans several lines
+    InvalidText,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnsupportedFormat => write!(f, "unsupported image format"),
+            Error::Image(err) => write!(f, "{err}"),
+            Error::Spec(err) => write!(f, "{err}"),
+            Error::OutOfBounds => write!(f, "crop rectangle is out of the image"),
+            Error::InvalidText => write!(f, "watermark text must be a non-empty UTF-8 line"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Self {
+        Error::Image(err)
+    }
+}
+
+impl From<stock0_spec::Error> for Error {
+    fn from(err: stock0_spec::Error) -> Self {
+        Error::Spec(err)
+    }
+}
+
+/// Detects the format from the magic bytes and decodes the image
+pub fn decode(bytes: &[u8]) -> Result<(ImageFormat, DynamicImage), Error> {
+    let format = ImageFormat::sniff(bytes).ok_or(Error::UnsupportedFormat)?;
+    let decoder_format = match format {
+        ImageFormat::Tiff => image::ImageFormat::Tiff,
+        ImageFormat::Png => image::ImageFormat::Png,
+        ImageFormat::Jpeg => image::ImageFormat::Jpeg,
+    };
+    let image = image::load_from_memory_with_format(bytes, decoder_format)?;
+    Ok((format, image))
+}
+
+/// PNG of the image, 8-bit grayscale if it is one and 8-bit RGB otherwise
+pub fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, Error> {
+    let mut buffer = Vec::new();
+    let encoder = PngEncoder::new(&mut buffer);
+    match image {
+        DynamicImage::ImageLuma8(luma) => {
+            encoder.write_image(luma, luma.width(), luma.height(), ExtendedColorType::L8)?
+        }
+        _ => {
+            let rgb = image.to_rgb8();
+            encoder.write_image(&rgb, rgb.width(), rgb.height(), ExtendedColorType::Rgb8)?
+        }
+    }
+    Ok(buffer)
+}
+
+/// 8-bit RGB encoding of the image in the thumbnail format, quality is only used by JPEG
+pub fn encode_rgb(
+    image: &DynamicImage,
+    format: ThumbnailFormat,
+    quality: u8,
+) -> Result<Vec<u8>, Error> {
+    // Drops the alpha channel (PNG) and expands grayscale (JPEG)
+    let rgb = image.to_rgb8();
+    let (width, height) = rgb.dimensions();
+
+    let mut buffer = Vec::new();
+    match format {
+        ThumbnailFormat::Png => PngEncoder::new(&mut buffer).write_image(
+            &rgb,
+            width,
+            height,
+            ExtendedColorType::Rgb8,
+        )?,
+        ThumbnailFormat::Jpeg => JpegEncoder::new_with_quality(&mut buffer, quality).write_image(
+            &rgb,
+            width,
+            height,
+            ExtendedColorType::Rgb8,
+        )?,
+        ThumbnailFormat::WebP => WebPEncoder::new_lossless(&mut buffer).write_image(
+            &rgb,
+            width,
+            height,
+            ExtendedColorType::Rgb8,
+        )?,
+    }
+    Ok(buffer)
+}
+
+/// Scales the image down (or up) to fit into `width x height` preserving its aspect ratio
+pub fn resize(image: &DynamicImage, width: u32, height: u32, filter: ResizeFilter) -> DynamicImage {
+    let filter = match filter {
+        ResizeFilter::Box => return image.thumbnail(width, height),
+        ResizeFilter::Nearest => FilterType::Nearest,
+        ResizeFilter::Triangle => FilterType::Triangle,
+        ResizeFilter::CatmullRom => FilterType::CatmullRom,
+        ResizeFilter::Gaussian => FilterType::Gaussian,
+        ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+    };
+    image.resize(width, height, filter)
+}
+
+/// Removes the details of the image as requested by the preview parameters, the preview keeps
+/// the size of the original and is always RGB
+pub fn preview(image: &DynamicImage, params: &PreviewParams) -> RgbImage {
+    let rgb = image.to_rgb8();
+    match params.effect {
+        PreviewEffect::Blur => imageops::blur(&rgb, params.strength as f32),
+        PreviewEffect::Pixelate => pixelate(&rgb, params.strength),
+    }
+}
+
+/// Fills every `block x block` square (clipped at the right and bottom edges) with its average color
+pub fn pixelate(image: &RgbImage, block: u32) -> RgbImage {
+    let (width, height) = image.dimensions();
+    let mut output = RgbImage::new(width, height);
+
+    for y0 in (0..height).step_by(block as usize) {
+        for x0 in (0..width).step_by(block as usize) {
+            let (x1, y1) = ((x0 + block).min(width), (y0 + block).min(height));
+
+            let mut sum = [0u64; 3];
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    for (acc, value) in sum.iter_mut().zip(image.get_pixel(x, y).0) {
+                        *acc += value as u64;
+                    }
+                }
+            }
+            let count = ((x1 - x0) * (y1 - y0)) as u64;
+            let average = Rgb(sum.map(|acc| ((acc + count / 2) / count) as u8));
+
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    output.put_pixel(x, y, average);
+                }
+            }
+        }
+    }
+    output
+}
+
+/// Overlays the watermark (image bytes or UTF-8 text, depending on the spec) on the image
+pub fn watermark(
+    image: &DynamicImage,
+    spec: &WatermarkSpec,
+    asset: &[u8],
+) -> Result<RgbaImage, Error> {
+    // Rasterize the watermark
+    let mut mark = match spec.kind {
+        WatermarkKind::Image => {
+            let (_, mark) = decode(asset)?;
+            let mark = mark.to_rgba8();
+            if spec.size == 0 {
+                mark
+            } else {
+                // Scale to the requested width preserving the aspect ratio
+                let height = (mark.height() as u64 * spec.size as u64 / mark.width() as u64).max(1);
+                imageops::resize(&mark, spec.size, height as u32, FilterType::Triangle)
+            }
+        }
+        WatermarkKind::Text => {
+            let text = std::str::from_utf8(asset).map_err(|_| Error::InvalidText)?;
+            render_text(text, spec.size as f32, spec.color)?
+        }
+    };
+
+    // Apply the opacity on top of the own transparency of the watermark
+    for pixel in mark.pixels_mut() {
+        pixel[3] = (pixel[3] as u16 * spec.opacity as u16 / 255) as u8;
+    }
+
+    // Blend the watermark at the requested position(s)
+    let mut output = image.to_rgba8();
+    for (x, y) in spec.placements(output.width(), output.height(), mark.width(), mark.height()) {
+        imageops::overlay(&mut output, &mark, x, y);
+    }
+    Ok(output)
+}
+
+/// Renders a single line of text with the bundled font, the background is transparent
+pub fn render_text(text: &str, size: f32, color: [u8; 3]) -> Result<RgbaImage, Error> {
+    if text.is_empty() || text.chars().any(char::is_control) {
+        return Err(Error::InvalidText);
+    }
+    let font = FontRef::try_from_slice(FONT).expect("bundled font is valid");
+    let scale = PxScale::from(size);
+    let scaled = font.as_scaled(scale);
+
+    // Lay out the glyphs along the baseline
+    let mut glyphs = Vec::new();
+    let mut caret = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        glyphs.push(id.with_scale_and_position(scale, point(caret, scaled.ascent())));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+    }
+
+    let width = (caret.ceil() as u32).max(1);
+    let height = ((scaled.ascent() - scaled.descent()).ceil() as u32).max(1);
+    let mut mark = RgbaImage::from_pixel(width, height, Rgba([color[0], color[1], color[2], 0]));
+
+    // Coverage of the outlines becomes the alpha channel
+    for glyph in glyphs {
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|x, y, coverage| {
+            let x = bounds.min.x as i64 + x as i64;
+            let y = bounds.min.y as i64 + y as i64;
+            if (0..width as i64).contains(&x) && (0..height as i64).contains(&y) {
+                let alpha = &mut mark.get_pixel_mut(x as u32, y as u32)[3];
+                *alpha = (*alpha).max((coverage.clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+        });
+    }
+    Ok(mark)
+}
+
+/// Applies a single operation of a transformation pipeline
+pub fn apply(image: DynamicImage, operation: &Operation) -> Result<DynamicImage, Error> {
+    let output = match operation {
+        Operation::Crop {
+            x,
+            y,
+            width,
+            height,
+        } => {
+            let fits = |offset: u32, len: u32, bound: u32| {
+                offset.checked_add(len).is_some_and(|end| end <= bound)
+            };
+            if !fits(*x, *width, image.width()) || !fits(*y, *height, image.height()) {
+                return Err(Error::OutOfBounds);
+            }
+            image.crop_imm(*x, *y, *width, *height)
+        }
+        Operation::Resize {
+            width,
+            height,
+            filter,
+        } => resize(&image, *width, *height, *filter),
+        Operation::Rotate(Rotation::Rotate90) => image.rotate90(),
+        Operation::Rotate(Rotation::Rotate180) => image.rotate180(),
+        Operation::Rotate(Rotation::Rotate270) => image.rotate270(),
+        Operation::ColorConvert(ColorMode::Grayscale) => DynamicImage::ImageLuma8(image.to_luma8()),
+        Operation::ColorConvert(ColorMode::Rgb) => DynamicImage::ImageRgb8(image.to_rgb8()),
+        Operation::Watermark { spec, asset } => {
+            DynamicImage::ImageRgba8(watermark(&image, spec, asset)?)
+        }
+    };
+    Ok(output)
+}
+
+/// Hash of the images and parameters committed by the programs
+pub fn sha256(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use image::GenericImageView;
+    use stock0_spec::Anchor;
+
+    use super::*;
+
+    /// Every pixel is distinct, so that the pixels can be traced through the operations
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([x as u8, y as u8, (x * y) as u8, 255])
+        }))
+    }
+
+    fn crop(x: u32, y: u32, width: u32, height: u32) -> Operation {
+        Operation::Crop {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn apply_crop() {
+        let image = gradient(10, 8);
+        let cropped = apply(image.clone(), &crop(2, 3, 4, 5)).unwrap();
+        assert_eq!(cropped.dimensions(), (4, 5));
+        assert_eq!(cropped.get_pixel(0, 0), image.get_pixel(2, 3));
+        assert_eq!(cropped.get_pixel(3, 4), image.get_pixel(5, 7));
+
+        // Up to the edges
+        let cropped = apply(image.clone(), &crop(6, 0, 4, 8)).unwrap();
+        assert_eq!(cropped.get_pixel(3, 7), image.get_pixel(9, 7));
+
+        for operation in [crop(7, 0, 4, 1), crop(0, 4, 1, 5), crop(u32::MAX, 0, 2, 1)] {
+            assert!(matches!(
+                apply(image.clone(), &operation),
+                Err(Error::OutOfBounds)
+            ));
+        }
+    }
+
+    #[test]
+    fn apply_rotate() {
+        let image = gradient(4, 2);
+        let rotate = |rotation| apply(image.clone(), &Operation::Rotate(rotation)).unwrap();
+
+        // Clockwise, the top left corner goes to the top right one
+        let rotated = rotate(Rotation::Rotate90);
+        assert_eq!(rotated.dimensions(), (2, 4));
+        assert_eq!(rotated.get_pixel(1, 0), image.get_pixel(0, 0));
+        assert_eq!(rotated.get_pixel(0, 3), image.get_pixel(3, 1));
+
+        let rotated = rotate(Rotation::Rotate180);
+        assert_eq!(rotated.dimensions(), (4, 2));
+        assert_eq!(rotated.get_pixel(0, 0), image.get_pixel(3, 1));
+        assert_eq!(rotated.get_pixel(3, 1), image.get_pixel(0, 0));
+
+        let rotated = rotate(Rotation::Rotate270);
+        assert_eq!(rotated.dimensions(), (2, 4));
+        assert_eq!(rotated.get_pixel(0, 0), image.get_pixel(3, 0));
+        assert_eq!(rotated.get_pixel(0, 3), image.get_pixel(0, 0));
+    }
+
+    #[test]
+    fn apply_color() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(3, 2, Rgba([255, 0, 0, 128])));
+
+        let gray = apply(
+            image.clone(),
+            &Operation::ColorConvert(ColorMode::Grayscale),
+        )
+        .unwrap();
+        let DynamicImage::ImageLuma8(gray) = gray else {
+            panic!("grayscale image expected")
+        };
+        assert_eq!(gray.dimensions(), (3, 2));
+        assert!(gray.pixels().all(|pixel| pixel[0] > 0 && pixel[0] < 255));
+
+        let rgb = apply(image, &Operation::ColorConvert(ColorMode::Rgb)).unwrap();
+        let DynamicImage::ImageRgb8(rgb) = rgb else {
+            panic!("RGB image expected")
+        };
+        assert!(rgb.pixels().all(|pixel| *pixel == Rgb([255, 0, 0])));
+    }
+
+    #[test]
+    fn resize_preserves_aspect_ratio() {
+        let image = gradient(40, 20);
+        for filter in [ResizeFilter::Box, ResizeFilter::Lanczos3] {
+            assert_eq!(resize(&image, 10, 10, filter).dimensions(), (10, 5));
+            assert_eq!(resize(&image, 30, 5, filter).dimensions(), (10, 5));
+            // Exact target of the same aspect ratio
+            assert_eq!(resize(&image, 20, 10, filter).dimensions(), (20, 10));
+        }
+        // Upscaling
+        assert_eq!(
+            resize(&image, 80, 80, ResizeFilter::Nearest).dimensions(),
+            (80, 40)
+        );
+
+        let resized = apply(
+            image,
+            &Operation::Resize {
+                width: 8,
+                height: 8,
+                filter: ResizeFilter::Triangle,
+            },
+        )
+        .unwrap();
+        assert_eq!(resized.dimensions(), (8, 4));
+    }
+
+    #[test]
+    fn encodes_png() {
+        let gray = DynamicImage::ImageLuma8(image::GrayImage::from_pixel(5, 3, image::Luma([7])));
+        let decoded = image::load_from_memory(&encode_png(&gray).unwrap()).unwrap();
+        assert_eq!(decoded.dimensions(), (5, 3));
+        assert_eq!(decoded.color(), image::ColorType::L8);
+
+        let decoded = image::load_from_memory(&encode_png(&gradient(6, 4)).unwrap()).unwrap();
+        assert_eq!(decoded.dimensions(), (6, 4));
+        assert_eq!(decoded.color(), image::ColorType::Rgb8);
+        assert_eq!(decoded, DynamicImage::ImageRgb8(gradient(6, 4).to_rgb8()));
+    }
+
+    #[test]
+    fn encodes_rgb() {
+        let image = gradient(9, 7);
+        for (format, quality, mime_format) in [
+            (ThumbnailFormat::Png, 0, image::ImageFormat::Png),
+            (ThumbnailFormat::Jpeg, 80, image::ImageFormat::Jpeg),
+            (ThumbnailFormat::WebP, 0, image::ImageFormat::WebP),
+        ] {
+            let bytes = encode_rgb(&image, format, quality).unwrap();
+            assert_eq!(image::guess_format(&bytes).unwrap(), mime_format);
+            let decoded = image::load_from_memory(&bytes).unwrap();
+            assert_eq!(decoded.dimensions(), (9, 7));
+            assert_eq!(decoded.color(), image::ColorType::Rgb8);
+        }
+    }
+
+    #[test]
+    fn renders_text() {
+        let mark = render_text("stock0", 24.0, [10, 20, 30]).unwrap();
+        assert!(mark.width() > mark.height() && mark.height() >= 24);
+        assert!(mark.pixels().all(|pixel| pixel.0[..3] == [10, 20, 30]));
+        assert!(mark.pixels().any(|pixel| pixel[3] == 255));
+        assert!(mark.pixels().any(|pixel| pixel[3] == 0));
+
+        for text in ["", "two\nlines"] {
+            assert!(matches!(
+                render_text(text, 24.0, [0; 3]),
+                Err(Error::InvalidText)
+            ));
+        }
+    }
+
+    /// Pixels of the output that differ from the original
+    fn changed(original: &DynamicImage, output: &RgbaImage) -> Vec<(u32, u32)> {
+        let original = original.to_rgba8();
+        original
+            .enumerate_pixels()
+            .filter(|(x, y, pixel)| output.get_pixel(*x, *y) != *pixel)
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+
+    #[test]
+    fn watermarks_text_within_placement() {
+        let image =
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(120, 60, Rgba([200, 200, 200, 255])));
+        let spec = WatermarkSpec {
+            kind: WatermarkKind::Text,
+            anchor: Anchor::TopLeft,
+            offset_x: 5,
+            offset_y: 6,
+            size: 16,
+            opacity: 255,
+            color: [0, 0, 0],
+        };
+        let mark = render_text("stock0", 16.0, [0, 0, 0]).unwrap();
+        let output = watermark(&image, &spec, b"stock0").unwrap();
+        assert_eq!(output.dimensions(), (120, 60));
+
+        let changed = changed(&image, &output);
+        assert!(!changed.is_empty());
+        assert!(changed.iter().all(
+            |&(x, y)| (5..5 + mark.width()).contains(&x) && (6..6 + mark.height()).contains(&y)
+        ));
+    }
+
+    #[test]
+    fn watermarks_image_within_placement() {
+        let image = gradient(20, 10);
+        let asset = encode_png(&DynamicImage::ImageRgb8(RgbImage::from_pixel(
+            4,
+            3,
+            Rgb([255, 0, 0]),
+        )))
+        .unwrap();
+        let spec = WatermarkSpec {
+            kind: WatermarkKind::Image,
+            anchor: Anchor::BottomRight,
+            offset_x: 1,
+            offset_y: 2,
+            size: 0,
+            opacity: 255,
+            color: [0; 3],
+        };
+        let output = watermark(&image, &spec, &asset).unwrap();
+
+        // Opaque, the rectangle is fully replaced and nothing else is touched
+        let inside = |x: u32, y: u32| (15..19).contains(&x) && (5..8).contains(&y);
+        for (x, y, pixel) in output.enumerate_pixels() {
+            if inside(x, y) {
+                assert_eq!(*pixel, Rgba([255, 0, 0, 255]), "pixel ({x}, {y})");
+            } else {
+                assert_eq!(*pixel, image.get_pixel(x, y), "pixel ({x}, {y})");
+            }
+        }
+
+        // Scaled to 8px wide, half transparent
+        let spec = WatermarkSpec {
+            size: 8,
+            opacity: 128,
+            ..spec
+        };
+        let output = watermark(&image, &spec, &asset).unwrap();
+        let changed = changed(&image, &output);
+        assert_eq!(changed.len(), 8 * 6);
+        assert!(changed
+            .iter()
+            .all(|&(x, y)| (11..19).contains(&x) && (2..8).contains(&y)));
+    }
+
+    #[test]
+    fn pixelate_averages_blocks() {
+        // Block of 2x2 with the channels summing to 1 + 2 + 2 + 2 = 7 -> 7 / 4 = 1.75 rounds to 2,
+        // 0 + 0 + 1 + 1 = 2 -> 0.5 rounds up to 1 and 10 + 11 + 11 + 11 = 43 -> 10.75 to 11
+        let image = RgbImage::from_fn(2, 2, |x, y| match (x, y) {
+            (0, 0) => Rgb([1, 0, 10]),
+            (1, 0) => Rgb([2, 0, 11]),
+            _ => Rgb([2, 1, 11]),
+        });
+        let output = pixelate(&image, 2);
+        assert!(output.pixels().all(|pixel| *pixel == Rgb([2, 1, 11])));
+    }
+
+    #[test]
+    fn pixelate_clips_edge_blocks() {
+        // 5x3 image with 2x2 blocks: the last column and the last row are blocks on their own
+        let image = RgbImage::from_fn(5, 3, |x, y| Rgb([(10 * x + y) as u8, x as u8, y as u8]));
+        let output = pixelate(&image, 2);
+        assert_eq!(output.dimensions(), (5, 3));
+
+        let source = &image;
+        let average = |xs: &[u32], ys: &[u32]| {
+            let pixels: Vec<_> = ys
+                .iter()
+                .flat_map(|&y| xs.iter().map(move |&x| source.get_pixel(x, y).0))
+                .collect();
+            let count = pixels.len() as u32;
+            Rgb([0, 1, 2].map(|c| {
+                let sum: u32 = pixels.iter().map(|p| p[c] as u32).sum();
+                ((sum + count / 2) / count) as u8
+            }))
+        };
+        for (xs, ys) in [
+            (&[0, 1][..], &[0, 1][..]),
+            (&[2, 3], &[0, 1]),
+            (&[4], &[0, 1]),
+            (&[0, 1], &[2]),
+            (&[4], &[2]),
+        ] {
+            let expected = average(xs, ys);
+            for &y in ys {
+                for &x in xs {
+                    assert_eq!(*output.get_pixel(x, y), expected, "pixel ({x}, {y})");
+                }
+            }
+        }
+        assert_eq!(*output.get_pixel(4, 2), *image.get_pixel(4, 2));
+    }
+
+    #[test]
+    fn preview_keeps_size() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(7, 5, Rgba([9, 8, 7, 0])));
+        for params in [
+            PreviewParams {
+                effect: PreviewEffect::Blur,
+                strength: 2,
+            },
+            PreviewParams {
+                effect: PreviewEffect::Pixelate,
+                strength: 4,
+            },
+        ] {
+            let output = preview(&image, &params);
+            assert_eq!(output.dimensions(), (7, 5));
+            assert_eq!(*output.get_pixel(3, 2), Rgb([9, 8, 7]));
+        }
+    }
+}
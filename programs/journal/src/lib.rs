@@ -0,0 +1,786 @@
+//! Public outputs (journals) of the guest programs, shared by the host and the guests.
+//!
+//! Guests commit [`Journal::to_bytes`], a plain concatenation of the fixed-size fields in the
+//! order they are declared, which is what Aligned hashes into the public input commitment.
+//! [`Journal::abi_encode`] produces the same fields as Solidity `abi.encode` so that a contract
+//! can `abi.decode` them, e.g. for the envelope journal:
+//!
+//! ```solidity
+//! (bytes32 imageHash, bytes32 blobCommitment, bytes memory publicKey, address buyer,
+//!     bytes12 deliveryNonce, bytes29 namespace, uint8 shareVersion, address signer) =
+//!     abi.decode(journal, (bytes32, bytes32, bytes, address, bytes12, bytes29, uint8, address));
+//! ```
+
+use std::fmt;
+
+/// Size of a SHA-256 digest, data root or blob commitment
+pub const HASH_SIZE: usize = 32;
+
+/// Size of a compressed SEC1 point
+pub const POINT_SIZE: usize = 33;
+
+/// Size of an Ethereum address
+pub const ADDRESS_SIZE: usize = 20;
+
+/// Size of a Celestia namespace (version + ID)
+pub const NAMESPACE_SIZE: usize = 29;
+
+/// Size of the blob signer address (share version 1)
+pub const SIGNER_SIZE: usize = 20;
+
+/// Size of the per-delivery nonce
+pub const NONCE_SIZE: usize = 12;
+
+/// Size of a RISC0 image ID (little endian words, as expected by Aligned)
+pub const IMAGE_ID_SIZE: usize = 32;
+
+/// Size of the encoded thumbnail spec (`stock0_spec::ThumbnailSpec`)
+pub const THUMBNAIL_SPEC_SIZE: usize = 28;
+
+/// Size of the encoded watermark spec (`stock0_spec::WatermarkSpec`)
+pub const WATERMARK_SPEC_SIZE: usize = 18;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    InvalidLength { expected: usize, actual: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidLength { expected, actual } => {
+                write!(f, "journal must be {expected} bytes, got {actual}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub trait Journal: Sized {
+    /// Length of the committed journal
+    const SIZE: usize;
+
+    fn to_bytes(&self) -> Vec<u8>;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error>;
+
+    /// Solidity ABI encoding of the journal fields (as a tuple)
+    fn abi_encode(&self) -> Vec<u8>;
+}
+
+/// Thumbnail program: the thumbnail was derived from the image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThumbnailJournal {
+    pub image_hash: [u8; HASH_SIZE],
+    pub thumbnail_hash: [u8; HASH_SIZE],
+    /// Format of the original image, sniffed from its magic bytes (`stock0_format::ImageFormat`)
+    pub image_format: u8,
+    /// Transformation applied to the image: crop, target size, output format and filter
+    pub thumbnail_spec: [u8; THUMBNAIL_SPEC_SIZE],
+}
+
+/// Watermark program: the output is the image with the watermark overlaid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatermarkJournal {
+    pub image_hash: [u8; HASH_SIZE],
+    /// Hash of the watermark asset: image bytes or UTF-8 text
+    pub watermark_hash: [u8; HASH_SIZE],
+    pub output_hash: [u8; HASH_SIZE],
+    /// Format of the original image (`stock0_format::ImageFormat`)
+    pub image_format: u8,
+    /// Kind, position, size, opacity and color of the watermark
+    pub watermark_spec: [u8; WATERMARK_SPEC_SIZE],
+}
+
+/// Preview program: the low-detail preview was derived from the image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviewJournal {
+    pub image_hash: [u8; HASH_SIZE],
+    /// Hash of the effect and its strength (`stock0_spec::PreviewParams`)
+    pub params_hash: [u8; HASH_SIZE],
+    pub preview_hash: [u8; HASH_SIZE],
+}
+
+/// Pipeline program: the output is the image transformed by the operation list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineJournal {
+    pub image_hash: [u8; HASH_SIZE],
+    /// Hash of the serialized operation list (`stock0_spec::Pipeline`)
+    pub pipeline_hash: [u8; HASH_SIZE],
+    pub output_hash: [u8; HASH_SIZE],
+}
+
+/// Envelope program: the blob is the image encrypted for the buyer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvelopeJournal {
+    pub image_hash: [u8; HASH_SIZE],
+    pub blob_commitment: [u8; HASH_SIZE],
+    pub public_key_h: [u8; POINT_SIZE],
+    /// Ethereum address of the buyer, derived from `public_key_h`
+    pub buyer_address: [u8; ADDRESS_SIZE],
+    pub delivery_nonce: [u8; NONCE_SIZE],
+    pub namespace: [u8; NAMESPACE_SIZE],
+    /// Celestia share version of the blob
+    pub share_version: u8,
+    /// Account that paid for the blob (share version 1), zeroed otherwise
+    pub signer: [u8; SIGNER_SIZE],
+}
+
+/// Delivery program: the image encrypted for the buyer was published under the data root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryJournal {
+    pub image_hash: [u8; HASH_SIZE],
+    pub data_root: [u8; HASH_SIZE],
+    pub public_key_h: [u8; POINT_SIZE],
+    /// Ethereum address of the buyer, derived from `public_key_h`
+    pub buyer_address: [u8; ADDRESS_SIZE],
+    pub delivery_nonce: [u8; NONCE_SIZE],
+    /// Share commitment of the blob, as returned by Celestia `blob.Get`
+    pub blob_commitment: [u8; HASH_SIZE],
+    pub namespace: [u8; NAMESPACE_SIZE],
+    /// Celestia share version of the blob
+    pub share_version: u8,
+    /// Account that paid for the blob (share version 1), zeroed otherwise
+    pub signer: [u8; SIGNER_SIZE],
+}
+
+/// Trade program: the thumbnail and the delivered blob were derived from the same image.
+///
+/// Both receipts are verified by the guest, so the image IDs of the thumbnail and delivery
+/// programs are committed and must be checked against the known ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeJournal {
+    pub thumbnail_image_id: [u8; IMAGE_ID_SIZE],
+    pub delivery_image_id: [u8; IMAGE_ID_SIZE],
+    pub image_hash: [u8; HASH_SIZE],
+    pub thumbnail_hash: [u8; HASH_SIZE],
+    pub image_format: u8,
+    pub thumbnail_spec: [u8; THUMBNAIL_SPEC_SIZE],
+    pub data_root: [u8; HASH_SIZE],
+    pub public_key_h: [u8; POINT_SIZE],
+    pub buyer_address: [u8; ADDRESS_SIZE],
+    pub delivery_nonce: [u8; NONCE_SIZE],
+    pub blob_commitment: [u8; HASH_SIZE],
+    pub namespace: [u8; NAMESPACE_SIZE],
+    pub share_version: u8,
+    pub signer: [u8; SIGNER_SIZE],
+}
+
+/// Blobshot program: the blob was published under the data root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobshotJournal {
+    pub data_root: [u8; HASH_SIZE],
+    pub blob_commitment: [u8; HASH_SIZE],
+    pub namespace: [u8; NAMESPACE_SIZE],
+}
+
+impl Journal for ThumbnailJournal {
+    const SIZE: usize = 2 * HASH_SIZE + 1 + THUMBNAIL_SPEC_SIZE;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        [
+            &self.image_hash[..],
+            &self.thumbnail_hash,
+            &[self.image_format],
+            &self.thumbnail_spec,
+        ]
+        .concat()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes, Self::SIZE)?;
+        Ok(ThumbnailJournal {
+            image_hash: reader.read(),
+            thumbnail_hash: reader.read(),
+            image_format: reader.read::<1>()[0],
+            thumbnail_spec: reader.read(),
+        })
+    }
+
+    fn abi_encode(&self) -> Vec<u8> {
+        abi_encode(&[
+            Token::FixedBytes(&self.image_hash),
+            Token::FixedBytes(&self.thumbnail_hash),
+            Token::Uint(self.image_format as u128),
+            Token::FixedBytes(&self.thumbnail_spec),
+        ])
+    }
+}
+
+impl Journal for WatermarkJournal {
+    const SIZE: usize = 3 * HASH_SIZE + 1 + WATERMARK_SPEC_SIZE;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        [
+            &self.image_hash[..],
+            &self.watermark_hash,
+            &self.output_hash,
+            &[self.image_format],
+            &self.watermark_spec,
+        ]
+        .concat()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes, Self::SIZE)?;
+        Ok(WatermarkJournal {
+            image_hash: reader.read(),
+            watermark_hash: reader.read(),
+            output_hash: reader.read(),
+            image_format: reader.read::<1>()[0],
+            watermark_spec: reader.read(),
+        })
+    }
+
+    fn abi_encode(&self) -> Vec<u8> {
+        abi_encode(&[
+            Token::FixedBytes(&self.image_hash),
+            Token::FixedBytes(&self.watermark_hash),
+            Token::FixedBytes(&self.output_hash),
+            Token::Uint(self.image_format as u128),
+            Token::FixedBytes(&self.watermark_spec),
+        ])
+    }
+}
+
+impl Journal for PreviewJournal {
+    const SIZE: usize = 3 * HASH_SIZE;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        [&self.image_hash[..], &self.params_hash, &self.preview_hash].concat()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes, Self::SIZE)?;
+        Ok(PreviewJournal {
+            image_hash: reader.read(),
+            params_hash: reader.read(),
+            preview_hash: reader.read(),
+        })
+    }
+
+    fn abi_encode(&self) -> Vec<u8> {
+        abi_encode(&[
+            Token::FixedBytes(&self.image_hash),
+            Token::FixedBytes(&self.params_hash),
+            Token::FixedBytes(&self.preview_hash),
+        ])
+    }
+}
+
+impl Journal for PipelineJournal {
+    const SIZE: usize = 3 * HASH_SIZE;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        [&self.image_hash[..], &self.pipeline_hash, &self.output_hash].concat()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes, Self::SIZE)?;
+        Ok(PipelineJournal {
+            image_hash: reader.read(),
+            pipeline_hash: reader.read(),
+            output_hash: reader.read(),
+        })
+    }
+
+    fn abi_encode(&self) -> Vec<u8> {
+        abi_encode(&[
+            Token::FixedBytes(&self.image_hash),
+            Token::FixedBytes(&self.pipeline_hash),
+            Token::FixedBytes(&self.output_hash),
+        ])
+    }
+}
+
+impl Journal for EnvelopeJournal {
+    const SIZE: usize =
+        2 * HASH_SIZE + POINT_SIZE + ADDRESS_SIZE + NONCE_SIZE + NAMESPACE_SIZE + 1 + SIGNER_SIZE;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        [
+            &self.image_hash[..],
+            &self.blob_commitment,
+            &self.public_key_h,
+            &self.buyer_address,
+            &self.delivery_nonce,
+            &self.namespace,
+            &[self.share_version],
+            &self.signer,
+        ]
+        .concat()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes, Self::SIZE)?;
+        Ok(EnvelopeJournal {
+            image_hash: reader.read(),
+            blob_commitment: reader.read(),
+            public_key_h: reader.read(),
+            buyer_address: reader.read(),
+            delivery_nonce: reader.read(),
+            namespace: reader.read(),
+            share_version: reader.read::<1>()[0],
+            signer: reader.read(),
+        })
+    }
+
+    fn abi_encode(&self) -> Vec<u8> {
+        abi_encode(&[
+            Token::FixedBytes(&self.image_hash),
+            Token::FixedBytes(&self.blob_commitment),
+            Token::Bytes(&self.public_key_h),
+            Token::Address(&self.buyer_address),
+            Token::FixedBytes(&self.delivery_nonce),
+            Token::FixedBytes(&self.namespace),
+            Token::Uint(self.share_version as u128),
+            Token::Address(&self.signer),
+        ])
+    }
+}
+
+impl Journal for DeliveryJournal {
+    const SIZE: usize =
+        3 * HASH_SIZE + POINT_SIZE + ADDRESS_SIZE + NONCE_SIZE + NAMESPACE_SIZE + 1 + SIGNER_SIZE;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        [
+            &self.image_hash[..],
+            &self.data_root,
+            &self.public_key_h,
+            &self.buyer_address,
+            &self.delivery_nonce,
+            &self.blob_commitment,
+            &self.namespace,
+            &[self.share_version],
+            &self.signer,
+        ]
+        .concat()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes, Self::SIZE)?;
+        Ok(DeliveryJournal {
+            image_hash: reader.read(),
+            data_root: reader.read(),
+            public_key_h: reader.read(),
+            buyer_address: reader.read(),
+            delivery_nonce: reader.read(),
+            blob_commitment: reader.read(),
+            namespace: reader.read(),
+            share_version: reader.read::<1>()[0],
+            signer: reader.read(),
+        })
+    }
+
+    fn abi_encode(&self) -> Vec<u8> {
+        abi_encode(&[
+            Token::FixedBytes(&self.image_hash),
+            Token::FixedBytes(&self.data_root),
+            Token::Bytes(&self.public_key_h),
+            Token::Address(&self.buyer_address),
+            Token::FixedBytes(&self.delivery_nonce),
+            Token::FixedBytes(&self.blob_commitment),
+            Token::FixedBytes(&self.namespace),
+            Token::Uint(self.share_version as u128),
+            Token::Address(&self.signer),
+        ])
+    }
+}
+
+impl Journal for BlobshotJournal {
+    const SIZE: usize = 2 * HASH_SIZE + NAMESPACE_SIZE;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        [&self.data_root[..], &self.blob_commitment, &self.namespace].concat()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes, Self::SIZE)?;
+        Ok(BlobshotJournal {
+            data_root: reader.read(),
+            blob_commitment: reader.read(),
+            namespace: reader.read(),
+        })
+    }
+
+    fn abi_encode(&self) -> Vec<u8> {
+        abi_encode(&[
+            Token::FixedBytes(&self.data_root),
+            Token::FixedBytes(&self.blob_commitment),
+            Token::FixedBytes(&self.namespace),
+        ])
+    }
+}
+
+impl TradeJournal {
+    /// Combines the journals of the thumbnail and delivery receipts, `None` unless they refer to
+    /// the same image
+    pub fn new(
+        thumbnail_image_id: [u8; IMAGE_ID_SIZE],
+        delivery_image_id: [u8; IMAGE_ID_SIZE],
+        thumbnail: &ThumbnailJournal,
+        delivery: &DeliveryJournal,
+    ) -> Option<Self> {
+        if thumbnail.image_hash != delivery.image_hash {
+            return None;
+        }
+        Some(TradeJournal {
+            thumbnail_image_id,
+            delivery_image_id,
+            image_hash: thumbnail.image_hash,
+            thumbnail_hash: thumbnail.thumbnail_hash,
+            image_format: thumbnail.image_format,
+            thumbnail_spec: thumbnail.thumbnail_spec,
+            data_root: delivery.data_root,
+            public_key_h: delivery.public_key_h,
+            buyer_address: delivery.buyer_address,
+            delivery_nonce: delivery.delivery_nonce,
+            blob_commitment: delivery.blob_commitment,
+            namespace: delivery.namespace,
+            share_version: delivery.share_version,
+            signer: delivery.signer,
+        })
+    }
+}
+
+impl Journal for TradeJournal {
+    const SIZE: usize =
+        2 * IMAGE_ID_SIZE + ThumbnailJournal::SIZE + DeliveryJournal::SIZE - HASH_SIZE;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        [
+            &self.thumbnail_image_id[..],
+            &self.delivery_image_id,
+            &self.image_hash,
+            &self.thumbnail_hash,
+            &[self.image_format],
+            &self.thumbnail_spec,
+            &self.data_root,
+            &self.public_key_h,
+            &self.buyer_address,
+            &self.delivery_nonce,
+            &self.blob_commitment,
+            &self.namespace,
+            &[self.share_version],
+            &self.signer,
+        ]
+        .concat()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(bytes, Self::SIZE)?;
+        Ok(TradeJournal {
+            thumbnail_image_id: reader.read(),
+            delivery_image_id: reader.read(),
+            image_hash: reader.read(),
+            thumbnail_hash: reader.read(),
+            image_format: reader.read::<1>()[0],
+            thumbnail_spec: reader.read(),
+            data_root: reader.read(),
+            public_key_h: reader.read(),
+            buyer_address: reader.read(),
+            delivery_nonce: reader.read(),
+            blob_commitment: reader.read(),
+            namespace: reader.read(),
+            share_version: reader.read::<1>()[0],
+            signer: reader.read(),
+        })
+    }
+
+    fn abi_encode(&self) -> Vec<u8> {
+        abi_encode(&[
+            Token::FixedBytes(&self.thumbnail_image_id),
+            Token::FixedBytes(&self.delivery_image_id),
+            Token::FixedBytes(&self.image_hash),
+            Token::FixedBytes(&self.thumbnail_hash),
+            Token::Uint(self.image_format as u128),
+            Token::FixedBytes(&self.thumbnail_spec),
+            Token::FixedBytes(&self.data_root),
+            Token::Bytes(&self.public_key_h),
+            Token::Address(&self.buyer_address),
+            Token::FixedBytes(&self.delivery_nonce),
+            Token::FixedBytes(&self.blob_commitment),
+            Token::FixedBytes(&self.namespace),
+            Token::Uint(self.share_version as u128),
+            Token::Address(&self.signer),
+        ])
+    }
+}
+
+/// Reads fixed-size fields from a journal of a known length
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], expected: usize) -> Result<Self, Error> {
+        if bytes.len() != expected {
+            return Err(Error::InvalidLength {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+        Ok(Reader { bytes })
+    }
+
+    fn read<const N: usize>(&mut self) -> [u8; N] {
+        let (field, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        field.try_into().expect("length is checked in Reader::new")
+    }
+}
+
+const WORD_SIZE: usize = 32;
+
+/// Solidity ABI value, see [`abi_encode`]
+pub enum Token<'a> {
+    /// `bytesN`, N <= 32, right padded
+    FixedBytes(&'a [u8]),
+    /// `uintN`, big endian
+    Uint(u128),
+    /// `address`, left padded
+    Address(&'a [u8; ADDRESS_SIZE]),
+    /// Dynamic `bytes`
+    Bytes(&'a [u8]),
+}
+
+/// `abi.encode` of the tokens, also the encoding of function call arguments
+pub fn abi_encode(tokens: &[Token]) -> Vec<u8> {
+    let mut head = Vec::with_capacity(tokens.len() * WORD_SIZE);
+    let mut tail = Vec::new();
+
+    for token in tokens {
+        match token {
+            Token::FixedBytes(value) => {
+                let mut word = [0u8; WORD_SIZE];
+                word[..value.len()].copy_from_slice(value);
+                head.extend_from_slice(&word);
+            }
+            Token::Uint(value) => head.extend_from_slice(&uint(*value)),
+            Token::Address(value) => {
+                let mut word = [0u8; WORD_SIZE];
+                word[WORD_SIZE - ADDRESS_SIZE..].copy_from_slice(*value);
+                head.extend_from_slice(&word);
+            }
+            Token::Bytes(value) => {
+                head.extend_from_slice(&uint((tokens.len() * WORD_SIZE + tail.len()) as u128));
+                tail.extend_from_slice(&uint(value.len() as u128));
+                tail.extend_from_slice(&padded(value));
+            }
+        }
+    }
+
+    head.extend_from_slice(&tail);
+    head
+}
+
+/// Right pads the value to a multiple of the word size
+fn padded(value: &[u8]) -> Vec<u8> {
+    let mut word = value.to_vec();
+    word.resize(value.len().div_ceil(WORD_SIZE) * WORD_SIZE, 0);
+    word
+}
+
+/// Big endian `uint256`
+fn uint(value: u128) -> [u8; WORD_SIZE] {
+    let mut word = [0u8; WORD_SIZE];
+    word[WORD_SIZE - 16..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Distinct filler per field, so that swapped fields are caught
+    fn field<const N: usize>(tag: u8) -> [u8; N] {
+        std::array::from_fn(|i| tag.wrapping_mul(16).wrapping_add(i as u8))
+    }
+
+    fn thumbnail() -> ThumbnailJournal {
+        ThumbnailJournal {
+            image_hash: field(1),
+            thumbnail_hash: field(2),
+            image_format: 3,
+            thumbnail_spec: field(4),
+        }
+    }
+
+    fn envelope() -> EnvelopeJournal {
+        EnvelopeJournal {
+            image_hash: field(1),
+            blob_commitment: field(2),
+            public_key_h: field(3),
+            buyer_address: field(4),
+            delivery_nonce: field(5),
+            namespace: field(6),
+            share_version: 1,
+            signer: field(7),
+        }
+    }
+
+    fn delivery() -> DeliveryJournal {
+        DeliveryJournal {
+            image_hash: field(1),
+            data_root: field(2),
+            public_key_h: field(3),
+            buyer_address: field(4),
+            delivery_nonce: field(5),
+            blob_commitment: field(6),
+            namespace: field(7),
+            share_version: 1,
+            signer: field(8),
+        }
+    }
+
+    fn roundtrip<J: Journal + PartialEq + fmt::Debug>(journal: J) {
+        let bytes = journal.to_bytes();
+        assert_eq!(bytes.len(), J::SIZE);
+        assert_eq!(J::from_bytes(&bytes).unwrap(), journal);
+
+        for len in [0, J::SIZE - 1, J::SIZE + 1] {
+            let mut bytes = bytes.clone();
+            bytes.resize(len, 0);
+            assert_eq!(
+                J::from_bytes(&bytes),
+                Err(Error::InvalidLength {
+                    expected: J::SIZE,
+                    actual: len
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn roundtrips() {
+        roundtrip(thumbnail());
+        roundtrip(WatermarkJournal {
+            image_hash: field(1),
+            watermark_hash: field(2),
+            output_hash: field(3),
+            image_format: 4,
+            watermark_spec: field(5),
+        });
+        roundtrip(PreviewJournal {
+            image_hash: field(1),
+            params_hash: field(2),
+            preview_hash: field(3),
+        });
+        roundtrip(PipelineJournal {
+            image_hash: field(1),
+            pipeline_hash: field(2),
+            output_hash: field(3),
+        });
+        roundtrip(envelope());
+        roundtrip(delivery());
+        roundtrip(BlobshotJournal {
+            data_root: field(1),
+            blob_commitment: field(2),
+            namespace: field(3),
+        });
+        roundtrip(TradeJournal::new(field(9), field(10), &thumbnail(), &delivery()).unwrap());
+    }
+
+    #[test]
+    fn market_offsets() {
+        // Market.sol reads the image hash at 0 and the buyer address at 97
+        for (bytes, journal_buyer) in [
+            (envelope().to_bytes(), envelope().buyer_address),
+            (delivery().to_bytes(), delivery().buyer_address),
+        ] {
+            assert_eq!(bytes[..HASH_SIZE], field::<HASH_SIZE>(1));
+            assert_eq!(bytes[97..97 + ADDRESS_SIZE], journal_buyer);
+        }
+    }
+
+    #[test]
+    fn trade_combines_journals() {
+        let (thumbnail, delivery) = (thumbnail(), delivery());
+        let trade = TradeJournal::new(field(9), field(10), &thumbnail, &delivery).unwrap();
+
+        // Image hash is shared, the rest of both journals follows the image IDs
+        let bytes = trade.to_bytes();
+        let (ids, rest) = bytes.split_at(2 * IMAGE_ID_SIZE);
+        assert_eq!(ids, [field::<IMAGE_ID_SIZE>(9), field(10)].concat());
+        let (thumbnail_bytes, delivery_bytes) = rest.split_at(ThumbnailJournal::SIZE);
+        assert_eq!(thumbnail_bytes, thumbnail.to_bytes());
+        assert_eq!(delivery_bytes, &delivery.to_bytes()[HASH_SIZE..]);
+
+        let other = DeliveryJournal {
+            image_hash: field(11),
+            ..delivery
+        };
+        assert_eq!(
+            TradeJournal::new(field(9), field(10), &thumbnail, &other),
+            None
+        );
+    }
+
+    #[test]
+    fn abi_static_tokens() {
+        let address = [0x11; ADDRESS_SIZE];
+        let encoded = abi_encode(&[
+            Token::FixedBytes(&[0xaa; 12]),
+            Token::Uint(0x0102),
+            Token::Address(&address),
+        ]);
+
+        let mut expected = vec![0u8; 3 * WORD_SIZE];
+        expected[..12].fill(0xaa);
+        expected[2 * WORD_SIZE - 2..2 * WORD_SIZE].copy_from_slice(&[1, 2]);
+        expected[3 * WORD_SIZE - ADDRESS_SIZE..].fill(0x11);
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn abi_dynamic_bytes() {
+        let encoded = abi_encode(&[
+            Token::Bytes(&[0xbb; 33]),
+            Token::Uint(7),
+            Token::Bytes(&[]),
+            Token::Bytes(&[0xcc; 32]),
+        ]);
+
+        let word = |bytes: &[u8], index: usize| bytes[index * WORD_SIZE..][..WORD_SIZE].to_vec();
+        // Head: offsets of the dynamic values, counted from the start of the encoding
+        assert_eq!(word(&encoded, 0), uint(4 * 32));
+        assert_eq!(word(&encoded, 1), uint(7));
+        assert_eq!(word(&encoded, 2), uint(7 * 32));
+        assert_eq!(word(&encoded, 3), uint(8 * 32));
+        // 33 bytes are padded to two words
+        assert_eq!(word(&encoded, 4), uint(33));
+        assert_eq!(word(&encoded, 5), vec![0xbb; 32]);
+        assert_eq!(word(&encoded, 6), [vec![0xbb], vec![0; 31]].concat());
+        // Empty value is just its length
+        assert_eq!(word(&encoded, 7), uint(0));
+        // Exact multiple of the word size is not padded
+        assert_eq!(word(&encoded, 8), uint(32));
+        assert_eq!(word(&encoded, 9), vec![0xcc; 32]);
+        assert_eq!(encoded.len(), 10 * WORD_SIZE);
+    }
+
+    #[test]
+    fn abi_delivery_journal() {
+        let journal = delivery();
+        let encoded = journal.abi_encode();
+
+        // 9 head words, the public key is the only dynamic field
+        assert_eq!(encoded.len(), 9 * WORD_SIZE + WORD_SIZE + 2 * WORD_SIZE);
+        assert_eq!(encoded[..HASH_SIZE], journal.image_hash);
+        assert_eq!(encoded[2 * WORD_SIZE..3 * WORD_SIZE], uint(9 * 32));
+        assert_eq!(
+            encoded[4 * WORD_SIZE - ADDRESS_SIZE..4 * WORD_SIZE],
+            journal.buyer_address
+        );
+        assert_eq!(
+            encoded[9 * WORD_SIZE..10 * WORD_SIZE],
+            uint(POINT_SIZE as u128)
+        );
+        assert_eq!(
+            encoded[10 * WORD_SIZE..10 * WORD_SIZE + POINT_SIZE],
+            journal.public_key_h
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use risc0_zkvm::guest::env;
+use std::io::Read;
+use stock0_imaging::sha256;
+use stock0_journal::{Journal, PipelineJournal};
+use stock0_spec::Pipeline;
+
+fn main() {
+    // Read the serialized operation list, only its hash is committed
+    let pipeline_len: u32 = env::read();
+    let mut pipeline_bytes = vec![0u8; pipeline_len as usize];
+    env::read_slice(&mut pipeline_bytes);
+    let pipeline = Pipeline::from_bytes(&pipeline_bytes).unwrap();
+
+    // Read raw image data
+    let mut image_bytes = Vec::<u8>::new();
+    env::stdin().read_to_end(&mut image_bytes).unwrap();
+
+    // Compute original image hash (has to match the C2PA claim)
+    let image_hash = sha256(&image_bytes);
+
+    // Decode the image and apply the operations in order
+    let (_, original) = stock0_imaging::decode(&image_bytes).unwrap();
+    let output = pipeline
+        .operations
+        .iter()
+        .try_fold(original, stock0_imaging::apply)
+        .unwrap();
+
+    // Grayscale output is kept as is, anything else is encoded as RGB
+    let buffer = stock0_imaging::encode_png(&output).unwrap();
+
+    // Write original image, operation list & output hashes to the journal
+    let journal = PipelineJournal {
+        image_hash,
+        pipeline_hash: sha256(&pipeline_bytes),
+        output_hash: sha256(&buffer),
+    };
+    env::commit_slice(&journal.to_bytes());
+
+    // Write resulting image to stdout
+    env::write_slice(&buffer);
+}
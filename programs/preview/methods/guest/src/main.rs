@@ -0,0 +1,39 @@
+use risc0_zkvm::guest::env;
+use std::io::Read;
+use stock0_imaging::sha256;
+use stock0_journal::{Journal, PreviewJournal};
+use stock0_spec::PreviewParams;
+
+fn main() {
+    // Read the effect to apply, only its hash is committed
+    let mut params_bytes = [0u8; PreviewParams::SIZE];
+    env::read_slice(&mut params_bytes);
+    let params = PreviewParams::from_bytes(&params_bytes).unwrap();
+
+    // Read raw image data
+    let mut image_bytes = Vec::<u8>::new();
+    env::stdin().read_to_end(&mut image_bytes).unwrap();
+
+    // Compute original image hash (has to match the C2PA claim)
+    let image_hash = sha256(&image_bytes);
+
+    // Decode the image from bytes
+    let (_, original) = stock0_imaging::decode(&image_bytes).unwrap();
+
+    // Remove the details, the preview keeps the size of the original and is always RGB
+    let preview = stock0_imaging::preview(&original, &params);
+
+    // Encode the preview as PNG
+    let buffer = stock0_imaging::encode_png(&preview.into()).unwrap();
+
+    // Write original image, parameters & preview hashes to the journal
+    let journal = PreviewJournal {
+        image_hash,
+        params_hash: sha256(&params.to_bytes()),
+        preview_hash: sha256(&buffer),
+    };
+    env::commit_slice(&journal.to_bytes());
+
+    // Write resulting preview to stdout
+    env::write_slice(&buffer);
+}
@@ -0,0 +1,60 @@
+//! Parameters of the image programs (thumbnail, watermark, preview and pipeline), shared by the
+//! host and the guest programs. They are committed in the journals as is or by their hash.
+//!
+//! All integers are big endian.
+
+use std::fmt;
+
+mod pipeline;
+mod preview;
+mod thumbnail;
+mod watermark;
+
+pub use pipeline::{ColorMode, Operation, Pipeline, Rotation, MAX_OPERATIONS, MAX_RESIZE_SIZE};
+pub use preview::{PreviewEffect, PreviewParams, MAX_BLUR_SIGMA, MAX_PIXEL_BLOCK};
+pub use thumbnail::{CropMode, ResizeFilter, ThumbnailFormat, ThumbnailSpec, MAX_THUMBNAIL_SIZE};
+pub use watermark::{Anchor, WatermarkKind, WatermarkSpec, MAX_WATERMARK_SIZE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    Truncated,
+    InvalidThumbnailSpec(&'static str),
+    InvalidWatermarkSpec(&'static str),
+    InvalidPreviewParams(&'static str),
+    InvalidPipeline(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "spec is truncated"),
+            Error::InvalidThumbnailSpec(reason) => write!(f, "invalid thumbnail spec: {reason}"),
+            Error::InvalidWatermarkSpec(reason) => write!(f, "invalid watermark spec: {reason}"),
+            Error::InvalidPreviewParams(reason) => write!(f, "invalid preview params: {reason}"),
+            Error::InvalidPipeline(reason) => write!(f, "invalid pipeline: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::Truncated);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
@@ -0,0 +1,411 @@
+//! Ordered list of operations executed by the pipeline program, its hash is committed in the
+//! journal.
+//!
+//! The list is encoded as the number of operations (1 byte) followed by the operations, each
+//! one is a tag and a payload (big endian):
+//!
+//! | Tag | Operation | Payload                                                         |
+//! |-----|-----------|-----------------------------------------------------------------|
+//! | 1   | crop      | `x`, `y`, `width`, `height` (4 bytes each)                      |
+//! | 2   | resize    | `width`, `height` (4 bytes each), [`ResizeFilter`] (1 byte)     |
+//! | 3   | rotate    | [`Rotation`] (1 byte)                                           |
+//! | 4   | color     | [`ColorMode`] (1 byte)                                          |
+//! | 5   | watermark | [`WatermarkSpec`] (18 bytes), asset length (4 bytes), asset     |
+//!
+//! The watermark asset (image bytes or UTF-8 text) is embedded, so the hash of the list binds it.
+
+use std::fmt;
+
+use crate::{Error, Reader, ResizeFilter, WatermarkKind, WatermarkSpec};
+
+/// Upper bound of the number of operations
+pub const MAX_OPERATIONS: usize = 16;
+
+/// Upper bound of the resize target width and height
+pub const MAX_RESIZE_SIZE: u32 = 4096;
+
+/// Clockwise rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Rotation {
+    Rotate90 = 1,
+    Rotate180 = 2,
+    Rotate270 = 3,
+}
+
+/// Color model the image is converted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ColorMode {
+    /// 8-bit luma, kept by the following operations except the watermark
+    Grayscale = 1,
+    /// 8-bit RGB, drops the alpha channel
+    Rgb = 2,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// Rectangle of the current image, must be within it
+    Crop {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    /// Scales to fit into `width x height` preserving the aspect ratio
+    Resize {
+        width: u32,
+        height: u32,
+        filter: ResizeFilter,
+    },
+    Rotate(Rotation),
+    ColorConvert(ColorMode),
+    /// Overlays the asset, image bytes or UTF-8 text depending on the spec
+    Watermark {
+        spec: WatermarkSpec,
+        asset: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    pub operations: Vec<Operation>,
+}
+
+impl Rotation {
+    pub fn from_u8(id: u8) -> Result<Self, Error> {
+        match id {
+            1 => Ok(Rotation::Rotate90),
+            2 => Ok(Rotation::Rotate180),
+            3 => Ok(Rotation::Rotate270),
+            _ => Err(Error::InvalidPipeline("unknown rotation")),
+        }
+    }
+
+    pub fn degrees(&self) -> u32 {
+        *self as u32 * 90
+    }
+}
+
+impl ColorMode {
+    pub fn from_u8(id: u8) -> Result<Self, Error> {
+        match id {
+            1 => Ok(ColorMode::Grayscale),
+            2 => Ok(ColorMode::Rgb),
+            _ => Err(Error::InvalidPipeline("unknown color mode")),
+        }
+    }
+}
+
+impl Operation {
+    pub fn validate(&self) -> Result<(), Error> {
+        match self {
+            Operation::Crop { width, height, .. } => {
+                if *width == 0 || *height == 0 {
+                    return Err(Error::InvalidPipeline("crop rectangle is empty"));
+                }
+            }
+            Operation::Resize { width, height, .. } => {
+                if !(1..=MAX_RESIZE_SIZE).contains(width) || !(1..=MAX_RESIZE_SIZE).contains(height)
+                {
+                    return Err(Error::InvalidPipeline("resize target is out of range"));
+                }
+            }
+            Operation::Rotate(_) | Operation::ColorConvert(_) => {}
+            Operation::Watermark { spec, asset } => {
+                spec.validate()?;
+                if asset.is_empty() {
+                    return Err(Error::InvalidPipeline("watermark asset is empty"));
+                }
+                if asset.len() > u32::MAX as usize {
+                    return Err(Error::InvalidPipeline("watermark asset is too long"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Operation::Crop {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                bytes.push(1);
+                for value in [x, y, width, height] {
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+            Operation::Resize {
+                width,
+                height,
+                filter,
+            } => {
+                bytes.push(2);
+                bytes.extend_from_slice(&width.to_be_bytes());
+                bytes.extend_from_slice(&height.to_be_bytes());
+                bytes.push(*filter as u8);
+            }
+            Operation::Rotate(rotation) => bytes.extend_from_slice(&[3, *rotation as u8]),
+            Operation::ColorConvert(mode) => bytes.extend_from_slice(&[4, *mode as u8]),
+            Operation::Watermark { spec, asset } => {
+                bytes.push(5);
+                bytes.extend_from_slice(&spec.to_bytes());
+                bytes.extend_from_slice(&(asset.len() as u32).to_be_bytes());
+                bytes.extend_from_slice(asset);
+            }
+        }
+    }
+
+    fn read(reader: &mut Reader) -> Result<Self, Error> {
+        let operation = match reader.u8()? {
+            1 => Operation::Crop {
+                x: reader.u32()?,
+                y: reader.u32()?,
+                width: reader.u32()?,
+                height: reader.u32()?,
+            },
+            2 => Operation::Resize {
+                width: reader.u32()?,
+                height: reader.u32()?,
+                filter: ResizeFilter::from_u8(reader.u8()?)?,
+            },
+            3 => Operation::Rotate(Rotation::from_u8(reader.u8()?)?),
+            4 => Operation::ColorConvert(ColorMode::from_u8(reader.u8()?)?),
+            5 => {
+                let spec = WatermarkSpec::from_bytes(reader.take(WatermarkSpec::SIZE)?)?;
+                let len = reader.u32()? as usize;
+                Operation::Watermark {
+                    spec,
+                    asset: reader.take(len)?.to_vec(),
+                }
+            }
+            _ => return Err(Error::InvalidPipeline("unknown operation")),
+        };
+        operation.validate()?;
+        Ok(operation)
+    }
+}
+
+impl Pipeline {
+    pub fn validate(&self) -> Result<(), Error> {
+        if !(1..=MAX_OPERATIONS).contains(&self.operations.len()) {
+            return Err(Error::InvalidPipeline(
+                "number of operations is out of range",
+            ));
+        }
+        self.operations.iter().try_for_each(Operation::validate)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.operations.len() as u8];
+        for operation in &self.operations {
+            operation.write(&mut bytes);
+        }
+        bytes
+    }
+
+    /// Decodes and validates the list, trailing bytes are rejected
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader(bytes);
+        let count = reader.u8()? as usize;
+        let operations = (0..count)
+            .map(|_| Operation::read(&mut reader))
+            .collect::<Result<_, _>>()?;
+        if !reader.0.is_empty() {
+            return Err(Error::InvalidPipeline("trailing bytes"));
+        }
+
+        let pipeline = Pipeline { operations };
+        pipeline.validate()?;
+        Ok(pipeline)
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Crop {
+                x,
+                y,
+                width,
+                height,
+            } => write!(f, "crop {width}x{height}+{x}+{y}"),
+            Operation::Resize {
+                width,
+                height,
+                filter,
+            } => write!(f, "resize {width}x{height} ({filter:?})"),
+            Operation::Rotate(rotation) => write!(f, "rotate {}", rotation.degrees()),
+            Operation::ColorConvert(mode) => write!(f, "color {mode:?}"),
+            Operation::Watermark { spec, asset } => match spec.kind {
+                WatermarkKind::Text => {
+                    write!(
+                        f,
+                        "watermark \"{}\" ({spec})",
+                        String::from_utf8_lossy(asset)
+                    )
+                }
+                WatermarkKind::Image => write!(f, "watermark {} bytes ({spec})", asset.len()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, operation) in self.operations.iter().enumerate() {
+            if i > 0 {
+                write!(f, " -> ")?;
+            }
+            write!(f, "{operation}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Anchor;
+
+    fn watermark() -> Operation {
+        Operation::Watermark {
+            spec: WatermarkSpec {
+                kind: WatermarkKind::Text,
+                anchor: Anchor::Center,
+                offset_x: 0,
+                offset_y: 0,
+                size: 32,
+                opacity: 96,
+                color: [255; 3],
+            },
+            asset: b"stock0".to_vec(),
+        }
+    }
+
+    fn pipeline() -> Pipeline {
+        Pipeline {
+            operations: vec![
+                Operation::Crop {
+                    x: 1,
+                    y: 2,
+                    width: 300,
+                    height: 200,
+                },
+                Operation::Resize {
+                    width: 150,
+                    height: 100,
+                    filter: ResizeFilter::CatmullRom,
+                },
+                Operation::Rotate(Rotation::Rotate270),
+                Operation::ColorConvert(ColorMode::Grayscale),
+                watermark(),
+            ],
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let pipeline = pipeline();
+        let bytes = pipeline.to_bytes();
+        assert_eq!(Pipeline::from_bytes(&bytes), Ok(pipeline));
+
+        // count, crop, resize, rotate, color, watermark (tag, spec, length, asset)
+        assert_eq!(bytes.len(), 1 + 17 + 10 + 2 + 2 + 1 + 18 + 4 + 6);
+        assert_eq!(bytes[..2], [5, 1]);
+        assert_eq!(bytes[18..28], [2, 0, 0, 0, 150, 0, 0, 0, 100, 3]);
+        assert_eq!(bytes[28..32], [3, 3, 4, 1]);
+        assert_eq!(bytes[51..55], 6u32.to_be_bytes());
+        assert_eq!(&bytes[55..], b"stock0");
+    }
+
+    #[test]
+    fn rejects_trailing_bytes() {
+        let mut bytes = pipeline().to_bytes();
+        bytes.push(0);
+        assert_eq!(
+            Pipeline::from_bytes(&bytes),
+            Err(Error::InvalidPipeline("trailing bytes"))
+        );
+
+        // Asset shorter than its length
+        let bytes = pipeline().to_bytes();
+        assert_eq!(
+            Pipeline::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::Truncated)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_count() {
+        assert_eq!(
+            Pipeline::from_bytes(&[0]),
+            Err(Error::InvalidPipeline(
+                "number of operations is out of range"
+            ))
+        );
+        assert_eq!(Pipeline::from_bytes(&[]), Err(Error::Truncated));
+
+        let too_long = Pipeline {
+            operations: vec![Operation::Rotate(Rotation::Rotate90); MAX_OPERATIONS + 1],
+        };
+        assert_eq!(
+            Pipeline::from_bytes(&too_long.to_bytes()),
+            Err(Error::InvalidPipeline(
+                "number of operations is out of range"
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_operations() {
+        let single = |operation: Operation| Pipeline {
+            operations: vec![operation],
+        };
+
+        let empty_crop = single(Operation::Crop {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 10,
+        });
+        assert_eq!(
+            Pipeline::from_bytes(&empty_crop.to_bytes()),
+            Err(Error::InvalidPipeline("crop rectangle is empty"))
+        );
+
+        for (width, height) in [(0, 10), (10, MAX_RESIZE_SIZE + 1)] {
+            let resize = single(Operation::Resize {
+                width,
+                height,
+                filter: ResizeFilter::Box,
+            });
+            assert_eq!(
+                Pipeline::from_bytes(&resize.to_bytes()),
+                Err(Error::InvalidPipeline("resize target is out of range"))
+            );
+        }
+
+        let Operation::Watermark { spec, .. } = watermark() else {
+            unreachable!()
+        };
+        let no_asset = single(Operation::Watermark {
+            spec,
+            asset: Vec::new(),
+        });
+        assert_eq!(
+            Pipeline::from_bytes(&no_asset.to_bytes()),
+            Err(Error::InvalidPipeline("watermark asset is empty"))
+        );
+
+        for bytes in [[1, 6, 0], [1, 3, 4], [1, 4, 3]] {
+            assert!(matches!(
+                Pipeline::from_bytes(&bytes),
+                Err(Error::InvalidPipeline(_))
+            ));
+        }
+    }
+}
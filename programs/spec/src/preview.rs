@@ -0,0 +1,148 @@
+//! Obfuscation applied by the preview program, its hash is committed in the journal.
+//!
+//! The parameters are encoded as a fixed-size record (big endian):
+//!
+//! | Field    | Size | Description                                     |
+//! |----------|------|-------------------------------------------------|
+//! | effect   | 1    | see [`PreviewEffect`]                           |
+//! | strength | 4    | blur: standard deviation, pixelate: block size  |
+
+use std::fmt;
+
+use crate::Error;
+
+/// Upper bound of the blur standard deviation, the cost grows linearly with it
+pub const MAX_BLUR_SIGMA: u32 = 64;
+
+/// Upper bound of the pixelation block size
+pub const MAX_PIXEL_BLOCK: u32 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PreviewEffect {
+    /// Gaussian blur
+    Blur = 1,
+    /// Every block is filled with its average color
+    Pixelate = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewParams {
+    pub effect: PreviewEffect,
+    /// Standard deviation of the blur or side of the pixelation blocks, px
+    pub strength: u32,
+}
+
+impl PreviewEffect {
+    pub fn from_u8(id: u8) -> Result<Self, Error> {
+        match id {
+            1 => Ok(PreviewEffect::Blur),
+            2 => Ok(PreviewEffect::Pixelate),
+            _ => Err(Error::InvalidPreviewParams("unknown effect")),
+        }
+    }
+}
+
+impl PreviewParams {
+    /// Length of the encoded parameters
+    pub const SIZE: usize = 5;
+
+    pub fn validate(&self) -> Result<(), Error> {
+        let valid = match self.effect {
+            PreviewEffect::Blur => (1..=MAX_BLUR_SIGMA).contains(&self.strength),
+            PreviewEffect::Pixelate => (2..=MAX_PIXEL_BLOCK).contains(&self.strength),
+        };
+        if !valid {
+            return Err(Error::InvalidPreviewParams("strength is out of range"));
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0] = self.effect as u8;
+        bytes[1..5].copy_from_slice(&self.strength.to_be_bytes());
+        bytes
+    }
+
+    /// Decodes and validates the parameters
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != Self::SIZE {
+            return Err(Error::Truncated);
+        }
+        let params = PreviewParams {
+            effect: PreviewEffect::from_u8(bytes[0])?,
+            strength: u32::from_be_bytes(bytes[1..5].try_into().unwrap()),
+        };
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+impl fmt::Display for PreviewParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.effect {
+            PreviewEffect::Blur => write!(f, "blur, sigma {}px", self.strength),
+            PreviewEffect::Pixelate => write!(f, "pixelate, {0}x{0}px blocks", self.strength),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        for params in [
+            PreviewParams {
+                effect: PreviewEffect::Blur,
+                strength: 12,
+            },
+            PreviewParams {
+                effect: PreviewEffect::Pixelate,
+                strength: MAX_PIXEL_BLOCK,
+            },
+        ] {
+            assert_eq!(PreviewParams::from_bytes(&params.to_bytes()), Ok(params));
+        }
+
+        let params = PreviewParams {
+            effect: PreviewEffect::Pixelate,
+            strength: 0x0102,
+        };
+        assert_eq!(params.to_bytes(), [2, 0, 0, 1, 2]);
+    }
+
+    #[test]
+    fn rejects_invalid_strength() {
+        for (effect, strength) in [
+            (PreviewEffect::Blur, 0),
+            (PreviewEffect::Blur, MAX_BLUR_SIGMA + 1),
+            (PreviewEffect::Pixelate, 1),
+            (PreviewEffect::Pixelate, MAX_PIXEL_BLOCK + 1),
+        ] {
+            let params = PreviewParams { effect, strength };
+            assert_eq!(
+                PreviewParams::from_bytes(&params.to_bytes()),
+                Err(Error::InvalidPreviewParams("strength is out of range"))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_encoding() {
+        assert_eq!(
+            PreviewParams::from_bytes(&[1, 0, 0, 0]),
+            Err(Error::Truncated)
+        );
+        assert_eq!(
+            PreviewParams::from_bytes(&[1, 0, 0, 0, 8, 0]),
+            Err(Error::Truncated)
+        );
+        assert_eq!(
+            PreviewParams::from_bytes(&[3, 0, 0, 0, 8]),
+            Err(Error::InvalidPreviewParams("unknown effect"))
+        );
+    }
+}
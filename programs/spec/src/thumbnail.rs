@@ -0,0 +1,452 @@
+//! Transformation applied by the thumbnail program, committed in its journal.
+//!
+//! The spec is encoded as a fixed-size record (big endian):
+//!
+//! | Field        | Size | Description                                        |
+//! |--------------|------|----------------------------------------------------|
+//! | width        | 4    | target width, px                                   |
+//! | height       | 4    | target height, px                                  |
+//! | crop mode    | 1    | `0` center, `1` none, `2` explicit rectangle       |
+//! | crop rect    | 16   | `x`, `y`, `width`, `height` (zeroed unless rect)   |
+//! | format       | 1    | see [`ThumbnailFormat`]                            |
+//! | quality      | 1    | JPEG quality `1..=100`, zero otherwise             |
+//! | filter       | 1    | see [`ResizeFilter`]                               |
+//!
+//! The cropped image is scaled to fit into `width x height` preserving its aspect ratio, so
+//! the center crop (which takes the aspect ratio of the target) gives the exact target size.
+
+use std::fmt;
+
+use crate::Error;
+
+/// Upper bound of the target width and height
+pub const MAX_THUMBNAIL_SIZE: u32 = 4096;
+
+/// Which part of the original image the thumbnail shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CropMode {
+    /// Largest centered rectangle with the aspect ratio of the target
+    Center,
+    /// Whole image
+    None,
+    /// Explicit rectangle, must be within the image
+    Rect {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// Encoding of the thumbnail, always 8-bit RGB
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ThumbnailFormat {
+    Png = 1,
+    Jpeg = 2,
+    /// Lossless
+    WebP = 3,
+}
+
+/// Resampling filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ResizeFilter {
+    /// Fast integer area averaging (`DynamicImage::thumbnail`)
+    Box = 0,
+    Nearest = 1,
+    Triangle = 2,
+    CatmullRom = 3,
+    Gaussian = 4,
+    Lanczos3 = 5,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThumbnailSpec {
+    pub width: u32,
+    pub height: u32,
+    pub crop: CropMode,
+    pub format: ThumbnailFormat,
+    /// JPEG quality, ignored by the other formats
+    pub quality: u8,
+    pub filter: ResizeFilter,
+}
+
+impl Default for ThumbnailSpec {
+    /// 75x75px centered square PNG
+    fn default() -> Self {
+        Self {
+            width: 75,
+            height: 75,
+            crop: CropMode::Center,
+            format: ThumbnailFormat::Png,
+            quality: 0,
+            filter: ResizeFilter::Box,
+        }
+    }
+}
+
+impl ThumbnailFormat {
+    pub fn from_u8(id: u8) -> Result<Self, Error> {
+        match id {
+            1 => Ok(ThumbnailFormat::Png),
+            2 => Ok(ThumbnailFormat::Jpeg),
+            3 => Ok(ThumbnailFormat::WebP),
+            _ => Err(Error::InvalidThumbnailSpec("unknown output format")),
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ThumbnailFormat::Png => "image/png",
+            ThumbnailFormat::Jpeg => "image/jpeg",
+            ThumbnailFormat::WebP => "image/webp",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ThumbnailFormat::Png => "png",
+            ThumbnailFormat::Jpeg => "jpg",
+            ThumbnailFormat::WebP => "webp",
+        }
+    }
+}
+
+impl ResizeFilter {
+    pub fn from_u8(id: u8) -> Result<Self, Error> {
+        match id {
+            0 => Ok(ResizeFilter::Box),
+            1 => Ok(ResizeFilter::Nearest),
+            2 => Ok(ResizeFilter::Triangle),
+            3 => Ok(ResizeFilter::CatmullRom),
+            4 => Ok(ResizeFilter::Gaussian),
+            5 => Ok(ResizeFilter::Lanczos3),
+            _ => Err(Error::InvalidThumbnailSpec("unknown resize filter")),
+        }
+    }
+}
+
+impl ThumbnailSpec {
+    /// Length of the encoded spec
+    pub const SIZE: usize = 28;
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if !(1..=MAX_THUMBNAIL_SIZE).contains(&self.width)
+            || !(1..=MAX_THUMBNAIL_SIZE).contains(&self.height)
+        {
+            return Err(Error::InvalidThumbnailSpec("target size is out of range"));
+        }
+        if let CropMode::Rect { width, height, .. } = self.crop {
+            if width == 0 || height == 0 {
+                return Err(Error::InvalidThumbnailSpec("crop rectangle is empty"));
+            }
+        }
+        let quality_valid = match self.format {
+            ThumbnailFormat::Jpeg => (1..=100).contains(&self.quality),
+            _ => self.quality == 0,
+        };
+        if !quality_valid {
+            return Err(Error::InvalidThumbnailSpec(
+                "quality must be 1-100 for JPEG and zero otherwise",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let (crop_mode, rect) = match self.crop {
+            CropMode::Center => (0, [0; 4]),
+            CropMode::None => (1, [0; 4]),
+            CropMode::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => (2, [x, y, width, height]),
+        };
+
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..4].copy_from_slice(&self.width.to_be_bytes());
+        bytes[4..8].copy_from_slice(&self.height.to_be_bytes());
+        bytes[8] = crop_mode;
+        for (i, value) in rect.iter().enumerate() {
+            bytes[9 + 4 * i..13 + 4 * i].copy_from_slice(&value.to_be_bytes());
+        }
+        bytes[25] = self.format as u8;
+        bytes[26] = self.quality;
+        bytes[27] = self.filter as u8;
+        bytes
+    }
+
+    /// Decodes and validates the spec
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != Self::SIZE {
+            return Err(Error::Truncated);
+        }
+        let u32_at =
+            |offset: usize| u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        let crop = match bytes[8] {
+            0 | 1 if bytes[9..25].iter().any(|b| *b != 0) => {
+                return Err(Error::InvalidThumbnailSpec("crop rectangle must be zeroed"))
+            }
+            0 => CropMode::Center,
+            1 => CropMode::None,
+            2 => CropMode::Rect {
+                x: u32_at(9),
+                y: u32_at(13),
+                width: u32_at(17),
+                height: u32_at(21),
+            },
+            _ => return Err(Error::InvalidThumbnailSpec("unknown crop mode")),
+        };
+
+        let spec = ThumbnailSpec {
+            width: u32_at(0),
+            height: u32_at(4),
+            crop,
+            format: ThumbnailFormat::from_u8(bytes[25])?,
+            quality: bytes[26],
+            filter: ResizeFilter::from_u8(bytes[27])?,
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Area of the original image to scale down, `(x, y, width, height)`
+    pub fn crop_rect(
+        &self,
+        image_width: u32,
+        image_height: u32,
+    ) -> Result<(u32, u32, u32, u32), Error> {
+        match self.crop {
+            CropMode::Center => {
+                // Widest rectangle of the target aspect ratio, `u64` to avoid overflows
+                let (width, height) = (self.width as u64, self.height as u64);
+                let crop_width = (image_width as u64).min(image_height as u64 * width / height);
+                let crop_height = (image_height as u64).min(image_width as u64 * height / width);
+                let crop_width = (crop_width as u32).max(1);
+                let crop_height = (crop_height as u32).max(1);
+                Ok((
+                    (image_width - crop_width) / 2,
+                    (image_height - crop_height) / 2,
+                    crop_width,
+                    crop_height,
+                ))
+            }
+            CropMode::None => Ok((0, 0, image_width, image_height)),
+            CropMode::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let fits = |offset: u32, len: u32, bound: u32| {
+                    offset.checked_add(len).is_some_and(|end| end <= bound)
+                };
+                if !fits(x, width, image_width) || !fits(y, height, image_height) {
+                    return Err(Error::InvalidThumbnailSpec(
+                        "crop rectangle is out of the image",
+                    ));
+                }
+                Ok((x, y, width, height))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ThumbnailSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}, crop ", self.width, self.height)?;
+        match self.crop {
+            CropMode::Center => write!(f, "center")?,
+            CropMode::None => write!(f, "none")?,
+            CropMode::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => write!(f, "{width}x{height}+{x}+{y}")?,
+        }
+        write!(f, ", {}", self.format.mime_type())?;
+        if self.format == ThumbnailFormat::Jpeg {
+            write!(f, " (quality {})", self.quality)?;
+        }
+        write!(f, ", filter {:?}", self.filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jpeg_rect() -> ThumbnailSpec {
+        ThumbnailSpec {
+            width: 320,
+            height: 200,
+            crop: CropMode::Rect {
+                x: 10,
+                y: 20,
+                width: 640,
+                height: 400,
+            },
+            format: ThumbnailFormat::Jpeg,
+            quality: 85,
+            filter: ResizeFilter::Lanczos3,
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        for spec in [ThumbnailSpec::default(), jpeg_rect()] {
+            assert_eq!(ThumbnailSpec::from_bytes(&spec.to_bytes()), Ok(spec));
+        }
+
+        let bytes = jpeg_rect().to_bytes();
+        assert_eq!(bytes[0..4], 320u32.to_be_bytes());
+        assert_eq!(bytes[8], 2);
+        assert_eq!(bytes[9..13], 10u32.to_be_bytes());
+        assert_eq!(bytes[21..25], 400u32.to_be_bytes());
+        assert_eq!(bytes[25..28], [2, 85, 5]);
+    }
+
+    #[test]
+    fn rejects_rect_with_other_modes() {
+        for crop_mode in [0, 1] {
+            let mut bytes = ThumbnailSpec::default().to_bytes();
+            bytes[8] = crop_mode;
+            bytes[20] = 1;
+            assert_eq!(
+                ThumbnailSpec::from_bytes(&bytes),
+                Err(Error::InvalidThumbnailSpec("crop rectangle must be zeroed"))
+            );
+        }
+
+        let mut bytes = ThumbnailSpec::default().to_bytes();
+        bytes[8] = 3;
+        assert_eq!(
+            ThumbnailSpec::from_bytes(&bytes),
+            Err(Error::InvalidThumbnailSpec("unknown crop mode"))
+        );
+
+        let mut bytes = ThumbnailSpec::default().to_bytes();
+        bytes[8] = 2;
+        assert_eq!(
+            ThumbnailSpec::from_bytes(&bytes),
+            Err(Error::InvalidThumbnailSpec("crop rectangle is empty"))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_quality() {
+        let png_with_quality = ThumbnailSpec {
+            quality: 50,
+            ..ThumbnailSpec::default()
+        };
+        for quality in [0, 101] {
+            let jpeg = ThumbnailSpec {
+                quality,
+                ..jpeg_rect()
+            };
+            for spec in [png_with_quality, jpeg] {
+                assert!(matches!(
+                    ThumbnailSpec::from_bytes(&spec.to_bytes()),
+                    Err(Error::InvalidThumbnailSpec(_))
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_size() {
+        for (width, height) in [(0, 75), (75, 0), (MAX_THUMBNAIL_SIZE + 1, 75)] {
+            let spec = ThumbnailSpec {
+                width,
+                height,
+                ..ThumbnailSpec::default()
+            };
+            assert_eq!(
+                ThumbnailSpec::from_bytes(&spec.to_bytes()),
+                Err(Error::InvalidThumbnailSpec("target size is out of range"))
+            );
+        }
+
+        let spec = ThumbnailSpec {
+            width: MAX_THUMBNAIL_SIZE,
+            height: MAX_THUMBNAIL_SIZE,
+            ..ThumbnailSpec::default()
+        };
+        assert_eq!(ThumbnailSpec::from_bytes(&spec.to_bytes()), Ok(spec));
+    }
+
+    #[test]
+    fn rejects_invalid_length() {
+        let bytes = ThumbnailSpec::default().to_bytes();
+        assert_eq!(
+            ThumbnailSpec::from_bytes(&bytes[..ThumbnailSpec::SIZE - 1]),
+            Err(Error::Truncated)
+        );
+        assert_eq!(
+            ThumbnailSpec::from_bytes(&[&bytes[..], &[0]].concat()),
+            Err(Error::Truncated)
+        );
+
+        let mut bytes = ThumbnailSpec::default().to_bytes();
+        bytes[25] = 0;
+        assert!(ThumbnailSpec::from_bytes(&bytes).is_err());
+        bytes[25] = 1;
+        bytes[27] = 6;
+        assert!(ThumbnailSpec::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn center_crop() {
+        let square = ThumbnailSpec::default();
+        assert_eq!(square.crop_rect(400, 300), Ok((50, 0, 300, 300)));
+        assert_eq!(square.crop_rect(300, 400), Ok((0, 50, 300, 300)));
+        assert_eq!(square.crop_rect(1, 1), Ok((0, 0, 1, 1)));
+
+        // 16:10 target
+        let wide = ThumbnailSpec {
+            width: 320,
+            height: 200,
+            ..ThumbnailSpec::default()
+        };
+        assert_eq!(wide.crop_rect(1000, 1000), Ok((0, 187, 1000, 625)));
+        assert_eq!(wide.crop_rect(1600, 500), Ok((400, 0, 800, 500)));
+
+        // Never collapses to an empty rectangle
+        let strip = ThumbnailSpec {
+            width: MAX_THUMBNAIL_SIZE,
+            height: 1,
+            ..ThumbnailSpec::default()
+        };
+        assert_eq!(strip.crop_rect(10, 10), Ok((0, 4, 10, 1)));
+    }
+
+    #[test]
+    fn explicit_crop() {
+        let none = ThumbnailSpec {
+            crop: CropMode::None,
+            ..ThumbnailSpec::default()
+        };
+        assert_eq!(none.crop_rect(400, 300), Ok((0, 0, 400, 300)));
+
+        let spec = jpeg_rect();
+        assert_eq!(spec.crop_rect(650, 420), Ok((10, 20, 640, 400)));
+        assert!(spec.crop_rect(649, 420).is_err());
+        assert!(spec.crop_rect(650, 419).is_err());
+
+        let overflowing = ThumbnailSpec {
+            crop: CropMode::Rect {
+                x: u32::MAX,
+                y: 0,
+                width: 1,
+                height: 1,
+            },
+            ..ThumbnailSpec::default()
+        };
+        assert!(overflowing.crop_rect(u32::MAX, 1).is_err());
+    }
+}
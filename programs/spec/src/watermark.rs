@@ -0,0 +1,335 @@
+//! Watermark overlay applied by the watermark program, committed in its journal.
+//!
+//! The spec is encoded as a fixed-size record (big endian):
+//!
+//! | Field    | Size | Description                                              |
+//! |----------|------|----------------------------------------------------------|
+//! | kind     | 1    | see [`WatermarkKind`]                                    |
+//! | anchor   | 1    | see [`Anchor`]                                           |
+//! | offset x | 4    | margin from the anchor, px (spacing between tiles)       |
+//! | offset y | 4    |                                                          |
+//! | size     | 4    | text: font size, image: target width (`0` keeps as is)  |
+//! | opacity  | 1    | `0` transparent to `255` opaque                          |
+//! | color    | 3    | text color, RGB (zeroed for images)                      |
+
+use std::fmt;
+
+use crate::Error;
+
+/// Upper bound of the font size and of the watermark width
+pub const MAX_WATERMARK_SIZE: u32 = 4096;
+
+/// What is overlaid: a seller supplied image or a text rendered with the bundled font
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WatermarkKind {
+    /// TIFF, PNG or JPEG, transparency is preserved
+    Image = 1,
+    /// UTF-8 text, single line
+    Text = 2,
+}
+
+/// Where the watermark is placed on the original image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Anchor {
+    TopLeft = 0,
+    TopRight = 1,
+    BottomLeft = 2,
+    BottomRight = 3,
+    Center = 4,
+    /// Repeated over the whole image
+    Tile = 5,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatermarkSpec {
+    pub kind: WatermarkKind,
+    pub anchor: Anchor,
+    /// Margin from the anchor (shift for the center), spacing between the tiles
+    pub offset_x: u32,
+    pub offset_y: u32,
+    /// Font size of the text, px; width the image is scaled to, `0` keeps the original size
+    pub size: u32,
+    pub opacity: u8,
+    /// Text color
+    pub color: [u8; 3],
+}
+
+impl WatermarkKind {
+    pub fn from_u8(id: u8) -> Result<Self, Error> {
+        match id {
+            1 => Ok(WatermarkKind::Image),
+            2 => Ok(WatermarkKind::Text),
+            _ => Err(Error::InvalidWatermarkSpec("unknown watermark kind")),
+        }
+    }
+}
+
+impl Anchor {
+    pub fn from_u8(id: u8) -> Result<Self, Error> {
+        match id {
+            0 => Ok(Anchor::TopLeft),
+            1 => Ok(Anchor::TopRight),
+            2 => Ok(Anchor::BottomLeft),
+            3 => Ok(Anchor::BottomRight),
+            4 => Ok(Anchor::Center),
+            5 => Ok(Anchor::Tile),
+            _ => Err(Error::InvalidWatermarkSpec("unknown anchor")),
+        }
+    }
+}
+
+impl WatermarkSpec {
+    /// Length of the encoded spec
+    pub const SIZE: usize = 18;
+
+    pub fn validate(&self) -> Result<(), Error> {
+        match self.kind {
+            WatermarkKind::Text => {
+                if !(1..=MAX_WATERMARK_SIZE).contains(&self.size) {
+                    return Err(Error::InvalidWatermarkSpec("font size is out of range"));
+                }
+            }
+            WatermarkKind::Image => {
+                if self.size > MAX_WATERMARK_SIZE {
+                    return Err(Error::InvalidWatermarkSpec(
+                        "watermark width is out of range",
+                    ));
+                }
+                if self.color != [0; 3] {
+                    return Err(Error::InvalidWatermarkSpec(
+                        "color must be zeroed for images",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0] = self.kind as u8;
+        bytes[1] = self.anchor as u8;
+        bytes[2..6].copy_from_slice(&self.offset_x.to_be_bytes());
+        bytes[6..10].copy_from_slice(&self.offset_y.to_be_bytes());
+        bytes[10..14].copy_from_slice(&self.size.to_be_bytes());
+        bytes[14] = self.opacity;
+        bytes[15..18].copy_from_slice(&self.color);
+        bytes
+    }
+
+    /// Decodes and validates the spec
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != Self::SIZE {
+            return Err(Error::Truncated);
+        }
+        let u32_at =
+            |offset: usize| u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        let spec = WatermarkSpec {
+            kind: WatermarkKind::from_u8(bytes[0])?,
+            anchor: Anchor::from_u8(bytes[1])?,
+            offset_x: u32_at(2),
+            offset_y: u32_at(6),
+            size: u32_at(10),
+            opacity: bytes[14],
+            color: bytes[15..18].try_into().unwrap(),
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Top left corners of the watermark copies on the image, might be (partially) outside
+    pub fn placements(
+        &self,
+        image_width: u32,
+        image_height: u32,
+        mark_width: u32,
+        mark_height: u32,
+    ) -> Vec<(i64, i64)> {
+        let (iw, ih) = (image_width as i64, image_height as i64);
+        let (mw, mh) = (mark_width as i64, mark_height as i64);
+        let (ox, oy) = (self.offset_x as i64, self.offset_y as i64);
+
+        let corner = match self.anchor {
+            Anchor::TopLeft => (ox, oy),
+            Anchor::TopRight => (iw - mw - ox, oy),
+            Anchor::BottomLeft => (ox, ih - mh - oy),
+            Anchor::BottomRight => (iw - mw - ox, ih - mh - oy),
+            Anchor::Center => ((iw - mw) / 2 + ox, (ih - mh) / 2 + oy),
+            Anchor::Tile => {
+                let (step_x, step_y) = ((mw + ox).max(1), (mh + oy).max(1));
+                let mut placements = Vec::new();
+                let mut y = 0;
+                while y < ih {
+                    let mut x = 0;
+                    while x < iw {
+                        placements.push((x, y));
+                        x += step_x;
+                    }
+                    y += step_y;
+                }
+                return placements;
+            }
+        };
+        vec![corner]
+    }
+}
+
+impl fmt::Display for WatermarkSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            WatermarkKind::Text => {
+                let [r, g, b] = self.color;
+                write!(f, "text {}px #{r:02x}{g:02x}{b:02x}", self.size)?
+            }
+            WatermarkKind::Image if self.size == 0 => write!(f, "image")?,
+            WatermarkKind::Image => write!(f, "image {}px wide", self.size)?,
+        }
+        write!(
+            f,
+            ", {:?} +{}+{}, opacity {}",
+            self.anchor, self.offset_x, self.offset_y, self.opacity
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text() -> WatermarkSpec {
+        WatermarkSpec {
+            kind: WatermarkKind::Text,
+            anchor: Anchor::BottomRight,
+            offset_x: 16,
+            offset_y: 8,
+            size: 48,
+            opacity: 128,
+            color: [0xff, 0x80, 0x00],
+        }
+    }
+
+    fn image() -> WatermarkSpec {
+        WatermarkSpec {
+            kind: WatermarkKind::Image,
+            anchor: Anchor::Tile,
+            offset_x: 0,
+            offset_y: 0,
+            size: 0,
+            opacity: 255,
+            color: [0; 3],
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        for spec in [text(), image()] {
+            assert_eq!(WatermarkSpec::from_bytes(&spec.to_bytes()), Ok(spec));
+        }
+
+        let bytes = text().to_bytes();
+        assert_eq!(bytes[0..2], [2, 3]);
+        assert_eq!(bytes[2..6], 16u32.to_be_bytes());
+        assert_eq!(bytes[10..14], 48u32.to_be_bytes());
+        assert_eq!(bytes[14..18], [128, 0xff, 0x80, 0x00]);
+    }
+
+    #[test]
+    fn rejects_invalid_size() {
+        for size in [0, MAX_WATERMARK_SIZE + 1] {
+            let spec = WatermarkSpec { size, ..text() };
+            assert_eq!(
+                WatermarkSpec::from_bytes(&spec.to_bytes()),
+                Err(Error::InvalidWatermarkSpec("font size is out of range"))
+            );
+        }
+
+        let spec = WatermarkSpec {
+            size: MAX_WATERMARK_SIZE + 1,
+            ..image()
+        };
+        assert_eq!(
+            WatermarkSpec::from_bytes(&spec.to_bytes()),
+            Err(Error::InvalidWatermarkSpec(
+                "watermark width is out of range"
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_colored_image() {
+        let spec = WatermarkSpec {
+            color: [0, 0, 1],
+            ..image()
+        };
+        assert_eq!(
+            WatermarkSpec::from_bytes(&spec.to_bytes()),
+            Err(Error::InvalidWatermarkSpec(
+                "color must be zeroed for images"
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_encoding() {
+        let bytes = text().to_bytes();
+        assert_eq!(
+            WatermarkSpec::from_bytes(&bytes[..WatermarkSpec::SIZE - 1]),
+            Err(Error::Truncated)
+        );
+        assert_eq!(
+            WatermarkSpec::from_bytes(&[&bytes[..], &[0]].concat()),
+            Err(Error::Truncated)
+        );
+
+        let mut bytes = text().to_bytes();
+        bytes[0] = 3;
+        assert_eq!(
+            WatermarkSpec::from_bytes(&bytes),
+            Err(Error::InvalidWatermarkSpec("unknown watermark kind"))
+        );
+        let mut bytes = text().to_bytes();
+        bytes[1] = 6;
+        assert_eq!(
+            WatermarkSpec::from_bytes(&bytes),
+            Err(Error::InvalidWatermarkSpec("unknown anchor"))
+        );
+    }
+
+    #[test]
+    fn placements() {
+        let at = |anchor| WatermarkSpec { anchor, ..text() };
+        assert_eq!(at(Anchor::TopLeft).placements(100, 80, 30, 20), [(16, 8)]);
+        assert_eq!(at(Anchor::TopRight).placements(100, 80, 30, 20), [(54, 8)]);
+        assert_eq!(
+            at(Anchor::BottomLeft).placements(100, 80, 30, 20),
+            [(16, 52)]
+        );
+        assert_eq!(
+            at(Anchor::BottomRight).placements(100, 80, 30, 20),
+            [(54, 52)]
+        );
+        assert_eq!(at(Anchor::Center).placements(100, 80, 30, 20), [(51, 38)]);
+
+        // Larger than the image, partially outside
+        assert_eq!(
+            at(Anchor::BottomRight).placements(100, 80, 200, 100),
+            [(-116, -28)]
+        );
+
+        // Tiles cover the image with the offsets as spacing
+        let tiles = WatermarkSpec {
+            anchor: Anchor::Tile,
+            offset_x: 10,
+            offset_y: 0,
+            ..text()
+        };
+        assert_eq!(
+            tiles.placements(100, 50, 40, 30),
+            [(0, 0), (50, 0), (0, 30), (50, 30)]
+        );
+        assert_eq!(image().placements(2, 2, 0, 0).len(), 4);
+    }
+}
@@ -1,63 +1,46 @@
-use image::codecs::png::PngEncoder;
-use image::{ExtendedColorType, ImageEncoder, ImageFormat};
 use risc0_zkvm::guest::env;
-use risc0_zkvm::sha::rust_crypto::{Digest as _, Sha256};
 use std::io::Read;
-
-const THUMB_SIZE_PX: u32 = 75;
+use stock0_imaging::sha256;
+use stock0_journal::{Journal, ThumbnailJournal};
+use stock0_spec::ThumbnailSpec;
 
 fn main() {
+    // Read the transformation to apply, it is committed as is
+    let mut spec_bytes = [0u8; ThumbnailSpec::SIZE];
+    env::read_slice(&mut spec_bytes);
+    let spec = ThumbnailSpec::from_bytes(&spec_bytes).unwrap();
+
     // Read raw image data
     let mut image_bytes = Vec::<u8>::new();
     env::stdin().read_to_end(&mut image_bytes).unwrap();
 
-    // Compute original image hash (has match the C2PA claim)
+    // Compute original image hash (has to match the C2PA claim)
     let image_hash = sha256(&image_bytes);
 
-    // Load uncomressed image from bytes
-    let mut original =
-        image::load_from_memory_with_format(&image_bytes, ImageFormat::Tiff).unwrap();
-
-    // Do necessary calculations to properly scale and crop to get a centered square
-    let width = original.width();
-    let height = original.height();
-    let (x, y, width, height) = if width < height {
-        (0, (height - width) / 2, width, width)
-    } else {
-        ((width - height) / 2, 0, height, height)
-    };
-
-    let thumbnail = original
-        .crop(x, y, width, height)
-        .thumbnail(THUMB_SIZE_PX, THUMB_SIZE_PX);
+    // Decode the image, the format detected from the magic bytes is committed along with the hashes
+    let (image_format, mut original) = stock0_imaging::decode(&image_bytes).unwrap();
 
-    // Create a buffer to hold the PNG data
-    let mut buffer = Vec::new();
+    // Crop as requested by the spec (centered rectangle of the target aspect ratio by default)
+    let (x, y, width, height) = spec.crop_rect(original.width(), original.height()).unwrap();
+    let cropped = original.crop(x, y, width, height);
 
-    // Create a new PngEncoder that writes to the buffer
-    let encoder = PngEncoder::new(&mut buffer);
+    // Scale down to fit the target size
+    let resized = stock0_imaging::resize(&cropped, spec.width, spec.height, spec.filter);
 
-    // Encode the brighter image as PNG and write to the buffer
-    encoder
-        .write_image(
-            thumbnail.as_bytes(),
-            thumbnail.width(),
-            thumbnail.height(),
-            ExtendedColorType::Rgb8,
-        )
-        .unwrap();
+    // Encode the thumbnail (always RGB) in the requested format
+    let buffer = stock0_imaging::encode_rgb(&resized, spec.format, spec.quality).unwrap();
 
     let thumbnail_hash = sha256(&buffer);
 
-    // Write original image & thumbnail hashes to the journal
-    env::commit_slice(&[image_hash, thumbnail_hash].concat());
+    // Write original image & thumbnail hashes, the image format and the spec to the journal
+    let journal = ThumbnailJournal {
+        image_hash,
+        thumbnail_hash,
+        image_format: image_format as u8,
+        thumbnail_spec: spec.to_bytes(),
+    };
+    env::commit_slice(&journal.to_bytes());
 
     // Write resulting thumbnail to stdout
     env::write_slice(&buffer);
 }
-
-fn sha256(bytes: &[u8]) -> Vec<u8> {
-    let mut hasher = Sha256::default();
-    hasher.update(&bytes);
-    hasher.finalize_reset().to_vec()
-}
@@ -0,0 +1,33 @@
+use risc0_zkvm::guest::env;
+use risc0_zkvm::sha::Digest;
+use stock0_journal::{DeliveryJournal, Journal, ThumbnailJournal, TradeJournal};
+
+fn main() {
+    // Read the image IDs of the thumbnail and delivery programs, they are committed as is
+    let thumbnail_image_id: Digest = env::read();
+    let delivery_image_id: Digest = env::read();
+
+    // Read the journals of the receipts
+    let mut thumbnail_journal = [0u8; ThumbnailJournal::SIZE];
+    env::read_slice(&mut thumbnail_journal);
+    let mut delivery_journal = [0u8; DeliveryJournal::SIZE];
+    env::read_slice(&mut delivery_journal);
+
+    // Both receipts are added as assumptions by the host, the composed receipt is only valid
+    // once they are verified
+    env::verify(thumbnail_image_id, &thumbnail_journal).unwrap();
+    env::verify(delivery_image_id, &delivery_journal).unwrap();
+
+    let thumbnail = ThumbnailJournal::from_bytes(&thumbnail_journal).unwrap();
+    let delivery = DeliveryJournal::from_bytes(&delivery_journal).unwrap();
+
+    // Thumbnail and delivered blob must be derived from the same image
+    let journal = TradeJournal::new(
+        thumbnail_image_id.into(),
+        delivery_image_id.into(),
+        &thumbnail,
+        &delivery,
+    )
+    .expect("Thumbnail and delivery refer to different images");
+    env::commit_slice(&journal.to_bytes());
+}
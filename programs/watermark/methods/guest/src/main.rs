@@ -0,0 +1,49 @@
+use risc0_zkvm::guest::env;
+use std::io::Read;
+use stock0_imaging::sha256;
+use stock0_journal::{Journal, WatermarkJournal};
+use stock0_spec::WatermarkSpec;
+
+fn main() {
+    // Read the overlay parameters, they are committed as is
+    let mut spec_bytes = [0u8; WatermarkSpec::SIZE];
+    env::read_slice(&mut spec_bytes);
+    let spec = WatermarkSpec::from_bytes(&spec_bytes).unwrap();
+
+    // Read the watermark asset: image bytes or UTF-8 text
+    let asset_len: u32 = env::read();
+    let mut asset = vec![0u8; asset_len as usize];
+    env::read_slice(&mut asset);
+
+    // Read raw image data
+    let mut image_bytes = Vec::<u8>::new();
+    env::stdin().read_to_end(&mut image_bytes).unwrap();
+
+    // Compute original image and watermark hashes
+    let image_hash = sha256(&image_bytes);
+    let watermark_hash = sha256(&asset);
+
+    // Decode the original, the format is committed along with the hashes
+    let (image_format, original) = stock0_imaging::decode(&image_bytes).unwrap();
+
+    // Rasterize the watermark and blend it at the requested position(s)
+    let output = stock0_imaging::watermark(&original, &spec, &asset).unwrap();
+
+    // Drop the alpha channel, the output is always an RGB PNG
+    let buffer = stock0_imaging::encode_png(&output.into()).unwrap();
+
+    let output_hash = sha256(&buffer);
+
+    // Write the hashes, the image format and the spec to the journal
+    let journal = WatermarkJournal {
+        image_hash,
+        watermark_hash,
+        output_hash,
+        image_format: image_format as u8,
+        watermark_spec: spec.to_bytes(),
+    };
+    env::commit_slice(&journal.to_bytes());
+
+    // Write resulting image to stdout
+    env::write_slice(&buffer);
+}